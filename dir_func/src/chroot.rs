@@ -0,0 +1,219 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::time::Duration;
+
+use crate::process;
+use crate::sandbox::{run_recorded, without_syncdeps};
+use crate::srcinfo;
+use crate::state::{aur_path_of, state_dir};
+
+// directory in the state dir with the clean build root and the copies for the builds
+pub const CHROOT_DIR: &str = "chroot";
+
+// the unprivileged user in the build root, makepkg refuses to run as root
+const BUILD_USER: &str = "builduser";
+
+// where the package dir is copied to in the build copy
+const BUILD_DIR: &str = "/build";
+
+// the empty PKGDEST of makepkg in the build copy, so only the package-files of this build are copied
+// back and not older ones copied in with the package dir
+const PKGDEST_DIR: &str = "/pkgdest";
+
+// the clean build root of the AUR dir, only containing base-devel
+pub fn chroot_root(aur_path: &Path) -> PathBuf {
+    state_dir(aur_path).join(CHROOT_DIR).join("root")
+}
+
+// the package dir and the commands of a build, the commands run for it are printed and recorded
+type Build<'a> = Option<(&'a Path, &'a mut Vec<String>)>;
+
+fn sudo(args: &[&str], build: Build) -> Result<(), String> {
+    let mut cmd = Command::new("sudo");
+    cmd.args(args);
+    let status = match build {
+        Some((pkg_dir, commands)) => run_recorded(pkg_dir, &mut cmd, None, commands),
+        None => process::run_status(&mut cmd, None),
+    }
+    .map_err(|err| format!("sudo {}: {}", args.join(" "), err))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("'{}' failed: {}", args.join(" "), status))
+    }
+}
+
+fn nspawn(root: &str, command: &[&str], build: Build) -> Result<(), String> {
+    let mut args = vec!["systemd-nspawn", "-q", "-D", root];
+    args.extend_from_slice(command);
+    sudo(&args, build)
+}
+
+// creates the build root with pacstrap or updates an existing one, CALLS SUDO!
+pub fn prepare_root(aur_path: &Path) -> Result<(), String> {
+    let root = chroot_root(aur_path);
+    let root_str = root.to_string_lossy().to_string();
+    if root.join("etc").join("makepkg.conf").is_file() {
+        println!("Updating the build root {}", root.display());
+        return nspawn(&root_str, &["pacman", "-Syu", "--noconfirm"], None);
+    }
+    println!("Creating the build root {}", root.display());
+    sudo(&["mkdir", "-p", &root_str], None)?;
+    sudo(&["pacstrap", "-c", &root_str, "base-devel"], None)?;
+    nspawn(&root_str, &["useradd", "-m", "-U", BUILD_USER], None)
+}
+
+// copies the built package-files from the PKGDEST of the copy into the package dir
+fn copy_artifacts(pkgdest: &Path, pkg_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut copied: Vec<PathBuf> = Vec::new();
+    let entries = fs::read_dir(pkgdest)
+        .map_err(|err| format!("couldn't read {}: {}", pkgdest.display(), err))?;
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.contains(".pkg.tar") || !entry.path().is_file() {
+            continue;
+        }
+        let target = pkg_dir.join(&name);
+        fs::copy(entry.path(), &target)
+            .map_err(|err| format!("couldn't copy {}: {}", name, err))?;
+        copied.push(target);
+    }
+    Ok(copied)
+}
+
+// builds the package in a fresh copy of the build root, which gets only the declared build
+// dependencies of the .SRCINFO. The build root has to be prepared with prepare_root, the
+// package-files are copied back to the package dir and the copy is removed. The timeout applies to
+// makepkg, the commands actually run are added to commands, CALLS SUDO!
+pub fn makepkg(
+    pkg_dir: &Path,
+    makepkg_args: &[String],
+    timeout: Option<Duration>,
+    commands: &mut Vec<String>,
+) -> Result<ExitStatus, String> {
+    let deps = srcinfo::read_srcinfo(pkg_dir)
+        .map_err(|err| format!("couldn't read the .SRCINFO: {}", err))?
+        .build_deps();
+    let pkg_dir = pkg_dir
+        .canonicalize()
+        .map_err(|err| format!("couldn't resolve {}: {}", pkg_dir.display(), err))?;
+    let root = chroot_root(aur_path_of(&pkg_dir));
+    let copy = build_copy(&root, &pkg_dir)?;
+    let copy_str = copy.to_string_lossy().to_string();
+
+    sudo(&["rm", "-rf", &copy_str], Some((&pkg_dir, commands)))?;
+    sudo(
+        &[
+            "cp",
+            "-a",
+            "--reflink=auto",
+            &root.to_string_lossy(),
+            &copy_str,
+        ],
+        Some((&pkg_dir, commands)),
+    )?;
+    let result = build_in_copy(&pkg_dir, &copy_str, &deps, makepkg_args, timeout, commands);
+    if let Err(err) = sudo(&["rm", "-rf", &copy_str], Some((&pkg_dir, commands))) {
+        println!("WARNING: couldn't remove the build copy: {}", err);
+    }
+    result
+}
+
+// the build copy of the package next to the build root, named after the package dir and not the
+// pkgbase of the .SRCINFO, which could lead the sudo rm and cp out of the chroot dir
+fn build_copy(root: &Path, pkg_dir: &Path) -> Result<PathBuf, String> {
+    let copy = pkg_dir
+        .file_name()
+        .map(|name| root.with_file_name(format!("build-{}", name.to_string_lossy())))
+        .ok_or_else(|| format!("{} has no directory name", pkg_dir.display()))?;
+    if copy.parent() != root.parent() {
+        return Err(format!("invalid build copy {}", copy.display()));
+    }
+    Ok(copy)
+}
+
+fn build_in_copy(
+    pkg_dir: &Path,
+    copy: &str,
+    deps: &[String],
+    makepkg_args: &[String],
+    timeout: Option<Duration>,
+    commands: &mut Vec<String>,
+) -> Result<ExitStatus, String> {
+    if !deps.is_empty() {
+        let mut install = vec!["pacman", "-S", "--needed", "--noconfirm", "--asdeps"];
+        install.extend(deps.iter().map(|dep| dep.as_str()));
+        nspawn(copy, &install, Some((pkg_dir, commands)))
+            .map_err(|err| format!("installing the dependencies failed: {}", err))?;
+    }
+    let pkg_src = format!("{}/.", pkg_dir.to_string_lossy());
+    let copy_build = format!("{}{}", copy, BUILD_DIR);
+    let copy_pkgdest = format!("{}{}", copy, PKGDEST_DIR);
+    sudo(
+        &["cp", "-a", "--no-preserve=ownership", &pkg_src, &copy_build],
+        Some((pkg_dir, commands)),
+    )?;
+    sudo(&["mkdir", "-p", &copy_pkgdest], Some((pkg_dir, commands)))?;
+    nspawn(
+        copy,
+        &["chown", "-R", BUILD_USER, BUILD_DIR, PKGDEST_DIR],
+        Some((pkg_dir, commands)),
+    )?;
+
+    let status = run_recorded(
+        pkg_dir,
+        Command::new("sudo")
+            .args(["systemd-nspawn", "-q", "-D", copy])
+            .arg(format!("--chdir={}", BUILD_DIR))
+            .arg(format!("--setenv=PKGDEST={}", PKGDEST_DIR))
+            .args(["-u", BUILD_USER, "makepkg"])
+            .args(without_syncdeps(makepkg_args)),
+        timeout,
+        commands,
+    )
+    .map_err(|err| format!("makepkg in the build root: {}", err))?;
+    if status.success() {
+        let copied = copy_artifacts(Path::new(&copy_pkgdest), pkg_dir)?;
+        println!(
+            "{}: copied {} package(s) from the build root",
+            pkg_dir.display(),
+            copied.len()
+        );
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_artifacts_test() {
+        let tmp_path = Path::new("/tmp/aur_helper_rs_test/copy_artifacts_test");
+        let _ = fs::remove_dir_all(tmp_path);
+        let pkgdest = tmp_path.join("copy").join("pkgdest");
+        let pkg_dir = tmp_path.join("aur").join("foo");
+        fs::create_dir_all(&pkgdest).unwrap();
+        fs::create_dir_all(&pkg_dir).unwrap();
+        fs::write(pkgdest.join("foo-1.0-1-any.pkg.tar.zst"), "pkg").unwrap();
+        fs::write(pkgdest.join("PKGBUILD"), "").unwrap();
+
+        let copied = copy_artifacts(&pkgdest, &pkg_dir).unwrap();
+        assert_eq!(copied, vec![pkg_dir.join("foo-1.0-1-any.pkg.tar.zst")]);
+        assert_eq!(fs::read_to_string(&copied[0]).unwrap(), "pkg");
+        assert!(!pkg_dir.join("PKGBUILD").exists());
+
+        assert_eq!(
+            chroot_root(Path::new("/aur")),
+            Path::new("/aur/.aur_helper/chroot/root")
+        );
+        assert_eq!(
+            build_copy(&chroot_root(Path::new("/aur")), Path::new("/aur/foo")).unwrap(),
+            Path::new("/aur/.aur_helper/chroot/build-foo")
+        );
+        assert!(build_copy(&chroot_root(Path::new("/aur")), Path::new("/aur/..")).is_err());
+
+        let _ = fs::remove_dir_all(tmp_path);
+    }
+}
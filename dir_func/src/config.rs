@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::{env, fs, io};
+
+// environment variable to overwrite the path of the config file
+pub const CONFIG_ENV: &str = "AUR_HELPER_CONFIG";
+
+// the config file of aur_helper, structured like the pacman.conf: the [options] section holds the
+// global settings, a section named after a pkgbase the settings of that package.
+// A key can be given multiple times, e.g. for multiple hooks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    options: HashMap<String, Vec<String>>,
+    packages: HashMap<String, HashMap<String, Vec<String>>>,
+}
+
+// $AUR_HELPER_CONFIG, $XDG_CONFIG_HOME/aur_helper/config or $HOME/.config/aur_helper/config
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(CONFIG_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_home.join("aur_helper").join("config"))
+}
+
+impl Config {
+    // loads the config file, a missing file results in an empty config
+    pub fn load() -> Result<Config, io::Error> {
+        match config_path() {
+            Some(path) if path.is_file() => Config::parse(&fs::read_to_string(path)?),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Config, io::Error> {
+        let mut config = Config::default();
+        let mut section: Option<String> = None;
+        for (line_nr, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = Some(name.trim().to_string());
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim().to_string(), value.trim().to_string()),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("config line {}: expected 'Key = value'", line_nr + 1),
+                    ))
+                }
+            };
+            let entries = match section.as_deref() {
+                Some("options") => &mut config.options,
+                Some(pkgbase) => config.packages.entry(pkgbase.to_string()).or_default(),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("config line {}: option outside of a section", line_nr + 1),
+                    ))
+                }
+            };
+            entries.entry(key).or_default().push(value);
+        }
+        Ok(config)
+    }
+
+    // the last value of a global option
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.options
+            .get(key)
+            .and_then(|values| values.last())
+            .map(|value| value.as_str())
+    }
+
+    // all values of a global option
+    pub fn get_all(&self, key: &str) -> Vec<String> {
+        self.options.get(key).cloned().unwrap_or_default()
+    }
+
+    // all values of an option in the section of the package
+    pub fn get_pkg_all(&self, pkgbase: &str, key: &str) -> Vec<String> {
+        self.packages
+            .get(pkgbase)
+            .and_then(|options| options.get(key))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn aur_dir(&self) -> Option<String> {
+        self.get("AurDir").map(expand_home)
+    }
+
+    // a timeout in seconds like 'UpdateTimeout = 300', 0 disables the timeout
+    pub fn timeout(&self, key: &str, default: Option<Duration>) -> Option<Duration> {
+        match self.get(key).map(|secs| secs.parse::<u64>()) {
+            Some(Ok(0)) => None,
+            Some(Ok(secs)) => Some(Duration::from_secs(secs)),
+            Some(Err(_)) => {
+                println!(
+                    "WARNING: {} isn't a number of seconds, using the default",
+                    key
+                );
+                default
+            }
+            None => default,
+        }
+    }
+
+    // directory with exported PGP keys (<fingerprint>.asc) to import from before a keyserver
+    pub fn keyring_dir(&self) -> Option<PathBuf> {
+        self.get("KeyringDir")
+            .map(|dir| PathBuf::from(expand_home(dir)))
+    }
+
+    // database of a local pacman repository with the built packages, kept in sync with repo-remove
+    pub fn local_repo(&self) -> Option<PathBuf> {
+        self.get("LocalRepo")
+            .map(|db| PathBuf::from(expand_home(db)))
+    }
+}
+
+// replaces a leading ~/ with the HOME dir
+fn expand_home(dir: &str) -> String {
+    match dir.strip_prefix("~/") {
+        Some(rest) => match env::var("HOME") {
+            Ok(home) => format!("{}/{}", home, rest),
+            Err(_) => dir.to_string(),
+        },
+        None => dir.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config_test() {
+        let config = Config::parse(
+            "# comment
+[options]
+AurDir = /tmp/aur
+PreBuild = ccache -C
+PreBuild = echo second
+
+[yofi-bin]
+PostBuild = ./sign.sh
+",
+        )
+        .unwrap();
+        assert_eq!(config.aur_dir().as_deref(), Some("/tmp/aur"));
+        assert_eq!(config.get("PreBuild"), Some("echo second"));
+        assert_eq!(config.get_all("PreBuild"), vec!["ccache -C", "echo second"]);
+        assert_eq!(
+            config.get_pkg_all("yofi-bin", "PostBuild"),
+            vec!["./sign.sh"]
+        );
+        assert!(config.get_pkg_all("other", "PostBuild").is_empty());
+
+        let config = Config::parse("[options]\nUpdateTimeout = 10\nBuildTimeout = 0\n").unwrap();
+        let default = Some(Duration::from_secs(300));
+        assert_eq!(
+            config.timeout("UpdateTimeout", default),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(config.timeout("BuildTimeout", default), None);
+        assert_eq!(config.timeout("InstallTimeout", default), default);
+
+        assert!(Config::parse("AurDir = /tmp").is_err());
+        assert!(Config::parse("[options]\nAurDir").is_err());
+    }
+}
@@ -0,0 +1,104 @@
+// the format of the absolute dates, overwritten by --date-format
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+// the UTC date and time of a unix timestamp as (year, month, day, hour, minute, second)
+pub fn utc_from_unix(secs: i64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = secs.div_euclid(86400);
+    let day_secs = secs.rem_euclid(86400) as u32;
+    // civil from days, see https://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (
+        year,
+        month,
+        day,
+        day_secs / 3600,
+        day_secs / 60 % 60,
+        day_secs % 60,
+    )
+}
+
+// formats a unix timestamp in UTC with the strftime-like format: %Y, %m, %d, %H, %M, %S, %s for
+// the timestamp itself and %% for %. Other characters are kept
+pub fn format_date(secs: i64, format: &str) -> String {
+    let (year, month, day, hour, minute, second) = utc_from_unix(secs);
+    let mut formatted = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            formatted.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => formatted.push_str(&format!("{:04}", year)),
+            Some('m') => formatted.push_str(&format!("{:02}", month)),
+            Some('d') => formatted.push_str(&format!("{:02}", day)),
+            Some('H') => formatted.push_str(&format!("{:02}", hour)),
+            Some('M') => formatted.push_str(&format!("{:02}", minute)),
+            Some('S') => formatted.push_str(&format!("{:02}", second)),
+            Some('s') => formatted.push_str(&secs.to_string()),
+            Some('%') => formatted.push('%'),
+            Some(other) => {
+                formatted.push('%');
+                formatted.push(other);
+            }
+            None => formatted.push('%'),
+        }
+    }
+    formatted
+}
+
+// formats a duration in seconds as a human readable age
+pub fn format_age(secs: u64) -> String {
+    let days = secs / (60 * 60 * 24);
+    let (count, unit) = match days {
+        0 => return "today".to_string(),
+        1..=31 => (days, "day"),
+        32..=365 => (days / 31, "month"),
+        _ => (days / 365, "year"),
+    };
+    if count == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", count, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_date_test() {
+        assert_eq!(utc_from_unix(0), (1970, 1, 1, 0, 0, 0));
+        assert_eq!(utc_from_unix(951782400), (2000, 2, 29, 0, 0, 0));
+        assert_eq!(utc_from_unix(-1), (1969, 12, 31, 23, 59, 59));
+        assert_eq!(
+            format_date(1700000000, "%Y-%m-%dT%H:%M:%SZ"),
+            "2023-11-14T22:13:20Z"
+        );
+        assert_eq!(format_date(1700000000, DEFAULT_DATE_FORMAT), "2023-11-14");
+        assert_eq!(format_date(1700000000, "%s %% %q"), "1700000000 % %q");
+    }
+
+    #[test]
+    fn format_age_test() {
+        let day = 60 * 60 * 24;
+        assert_eq!(format_age(0), "today");
+        assert_eq!(format_age(day), "1 day ago");
+        assert_eq!(format_age(2 * day), "2 days ago");
+        assert_eq!(format_age(32 * day), "1 month ago");
+        assert_eq!(format_age(61 * day), "1 month ago");
+        assert_eq!(format_age(62 * day), "2 months ago");
+        assert_eq!(format_age(365 * day), "11 months ago");
+        assert_eq!(format_age(366 * day), "1 year ago");
+        assert_eq!(format_age(800 * day), "2 years ago");
+    }
+}
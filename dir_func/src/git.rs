@@ -0,0 +1,696 @@
+use std::fmt;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::{io, str::from_utf8};
+
+use crate::process;
+
+// state of a package repo compared to its last fetched upstream, untracked files are ignored
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GitStatus {
+    pub detached: bool,
+    pub dirty: bool,
+    pub has_upstream: bool,
+    pub ahead: u32,
+    pub behind: u32,
+}
+
+impl fmt::Display for GitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut states: Vec<String> = Vec::new();
+        if self.detached {
+            states.push("detached".to_string());
+        } else if !self.has_upstream {
+            states.push("no upstream".to_string());
+        }
+        if self.dirty {
+            states.push("dirty".to_string());
+        }
+        if self.ahead > 0 {
+            states.push(format!("ahead {}", self.ahead));
+        }
+        if self.behind > 0 {
+            states.push(format!("behind {}", self.behind));
+        }
+        if states.is_empty() {
+            states.push("clean".to_string());
+        }
+        write!(f, "{}", states.join(", "))
+    }
+}
+
+// runs a git command in the dir and returns stdout, fails on a non zero exit status
+pub fn git_output(dir: &Path, args: &[&str]) -> Result<String, io::Error> {
+    git_output_timeout(dir, args, None)
+}
+
+// git_output, which kills git after the timeout
+pub fn git_output_timeout(
+    dir: &Path,
+    args: &[&str],
+    timeout: Option<Duration>,
+) -> Result<String, io::Error> {
+    let output = process::run_output(Command::new("git").args(args).current_dir(dir), timeout);
+    git_result(args, output)
+}
+
+// git_output_timeout for the network commands, which may prompt on the terminal, e.g. ssh for the
+// host key or a passphrase
+pub fn git_output_interactive(
+    dir: &Path,
+    args: &[&str],
+    timeout: Option<Duration>,
+) -> Result<String, io::Error> {
+    let output =
+        process::run_output_interactive(Command::new("git").args(args).current_dir(dir), timeout);
+    git_result(args, output)
+}
+
+fn git_result(args: &[&str], output: Result<Output, io::Error>) -> Result<String, io::Error> {
+    let output = output.map_err(|err| match err.kind() {
+        io::ErrorKind::TimedOut => {
+            io::Error::new(err.kind(), format!("git {} {}", args.join(" "), err))
+        }
+        _ => err,
+    })?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "git {} failed ({}): {}",
+            args.join(" "),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    from_utf8(&output.stdout)
+        .map(|out| out.to_string())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+// stderr messages of git, curl and ssh for failures of the network or the server
+const TRANSIENT_ERRORS: [&str; 12] = [
+    "could not resolve host",
+    "connection timed out",
+    "connection refused",
+    "connection reset",
+    "failed to connect",
+    "operation timed out",
+    "the remote end hung up unexpectedly",
+    "early eof",
+    "rpc failed",
+    "returned error: 5",
+    "gnutls",
+    "ssl_",
+];
+
+// authentication failures aren't fixed by a retry
+const AUTH_ERRORS: [&str; 5] = [
+    "authentication failed",
+    "permission denied",
+    "could not read username",
+    "returned error: 401",
+    "returned error: 403",
+];
+
+// a git failure from the network, which may succeed on a retry: a timeout or a fatal error (exit
+// status 128) with a network message. Auth, merge and local errors aren't transient
+pub fn is_transient(err: &io::Error) -> bool {
+    if err.kind() == io::ErrorKind::TimedOut {
+        return true;
+    }
+    let msg = err.to_string().to_lowercase();
+    msg.contains("exit status: 128")
+        && TRANSIENT_ERRORS.iter().any(|pattern| msg.contains(pattern))
+        && !AUTH_ERRORS.iter().any(|pattern| msg.contains(pattern))
+}
+
+// how often a transient network failure is retried, the delay doubles after each attempt
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub delay: Duration,
+}
+
+// runs op until it succeeds, fails with a not transient error or the retries are used up.
+// Returns the result and the number of attempts
+pub fn retry<T>(
+    policy: &RetryPolicy,
+    mut op: impl FnMut() -> Result<T, io::Error>,
+) -> (Result<T, io::Error>, u32) {
+    let mut attempts = 0;
+    let mut delay = policy.delay;
+    loop {
+        attempts += 1;
+        match op() {
+            Err(err)
+                if attempts <= policy.retries && is_transient(&err) && !process::cancelled() =>
+            {
+                println!(
+                    "WARNING: attempt {} failed, retrying in {}s: {}",
+                    attempts,
+                    delay.as_secs(),
+                    err
+                );
+                let start = Instant::now();
+                while start.elapsed() < delay {
+                    if process::cancelled() {
+                        return (Err(err), attempts);
+                    }
+                    thread::sleep(Duration::from_millis(100).min(delay));
+                }
+                delay *= 2;
+            }
+            result => return (result, attempts),
+        }
+    }
+}
+
+// the settings for fetching and cloning from the AUR
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FetchOptions {
+    pub timeout: Option<Duration>,
+    pub retry: RetryPolicy,
+}
+
+pub fn parse_status(porcelain: &str) -> GitStatus {
+    let mut status = GitStatus::default();
+    for line in porcelain.lines() {
+        match line.strip_prefix("# ") {
+            Some(header) => {
+                if header == "branch.head (detached)" {
+                    status.detached = true;
+                } else if header.starts_with("branch.upstream ") {
+                    status.has_upstream = true;
+                } else if let Some(ab) = header.strip_prefix("branch.ab ") {
+                    for count in ab.split_whitespace() {
+                        if let Some(ahead) = count.strip_prefix('+') {
+                            status.ahead = ahead.parse().unwrap_or(0);
+                        } else if let Some(behind) = count.strip_prefix('-') {
+                            status.behind = behind.parse().unwrap_or(0);
+                        }
+                    }
+                }
+            }
+            None => {
+                if !line.is_empty() {
+                    status.dirty = true;
+                }
+            }
+        }
+    }
+    status
+}
+
+// reads the git state of the dir without fetching or changing anything
+pub fn get_status(dir: &Path) -> Result<GitStatus, io::Error> {
+    let porcelain = git_output(
+        dir,
+        &[
+            "--no-optional-locks",
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "--untracked-files=no",
+        ],
+    )?;
+    Ok(parse_status(&porcelain))
+}
+
+// unix time the current HEAD commit was reached (clone, pull, reset to an other commit, ...) from
+// the reflog, falls back to the commit time of HEAD. Resets to the same commit like reverting the
+// patches don't count
+pub fn head_integrated_time(dir: &Path) -> Result<u64, io::Error> {
+    let reflog = git_output(dir, &["log", "-g", "--format=%H %gd", "--date=unix"])?;
+    let mut entries = reflog.lines().filter_map(|line| {
+        let (hash, selector) = line.split_once(' ')?;
+        let time = selector.split_once('{')?.1.strip_suffix('}')?;
+        Some((hash, time))
+    });
+    let time = match entries.next() {
+        Some((head, time)) => entries
+            .take_while(|(hash, _)| *hash == head)
+            .last()
+            .map_or(time, |(_, time)| time)
+            .to_string(),
+        None => git_output(dir, &["log", "-1", "--format=%ct"])?
+            .trim()
+            .to_string(),
+    };
+    time.parse()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+// how to update a package repo, that can't simply be fast-forwarded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpdateStrategy {
+    Stash,
+    Rebase,
+    Reset,
+    Skip,
+}
+
+impl FromStr for UpdateStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stash" => Ok(Self::Stash),
+            "rebase" => Ok(Self::Rebase),
+            "reset" => Ok(Self::Reset),
+            "skip" => Ok(Self::Skip),
+            _ => Err(format!("unknown update strategy '{}'", s)),
+        }
+    }
+}
+
+// local state, which prevents a fast-forward to the fetched upstream
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Divergence {
+    pub dirty: bool,
+    pub local_commits: u32,
+    pub rewritten: bool,
+}
+
+impl Divergence {
+    pub fn is_clean(&self) -> bool {
+        !self.dirty && self.local_commits == 0 && !self.rewritten
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut problems: Vec<String> = Vec::new();
+        if self.dirty {
+            problems.push("uncommitted changes".to_string());
+        }
+        if self.local_commits > 0 {
+            problems.push(format!("{} local commit(s)", self.local_commits));
+        }
+        if self.rewritten {
+            problems.push("upstream history was rewritten (force-push)".to_string());
+        }
+        write!(f, "{}", problems.join(", "))
+    }
+}
+
+// what update_repo did with a package repo
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateResult {
+    UpToDate,
+    Updated(String),
+    Skipped(Divergence),
+}
+
+fn rev_parse(dir: &Path, rev: &str) -> Result<String, io::Error> {
+    Ok(git_output(dir, &["rev-parse", "--verify", rev])?
+        .trim()
+        .to_string())
+}
+
+fn rev_count(dir: &Path, range: &str) -> Result<u32, io::Error> {
+    git_output(dir, &["rev-list", "--count", range])?
+        .trim()
+        .parse()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+// only exit code 1 means not an ancestor, other failures like a missing object after a shallow
+// fetch are errors, so they don't count as a rewritten history
+fn is_ancestor(dir: &Path, ancestor: &str, rev: &str) -> Result<bool, io::Error> {
+    let output = Command::new("git")
+        .args(["merge-base", "--is-ancestor", ancestor, rev])
+        .current_dir(dir)
+        .output()?;
+    match output.status.code() {
+        Some(0) => Ok(true),
+        Some(1) => Ok(false),
+        _ => Err(io::Error::other(format!(
+            "git merge-base --is-ancestor {} {} failed with {}: {}",
+            ancestor,
+            rev,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))),
+    }
+}
+
+// fetches the upstream of the package repo, retried after transient failures, and integrates it.
+// Asks choose_strategy how to handle uncommitted changes, local commits or a rewritten upstream
+// history. Returns the result and the number of fetch attempts
+pub fn update_repo(
+    dir: &Path,
+    fetch: &FetchOptions,
+    choose_strategy: &mut dyn FnMut(&Path, &Divergence) -> UpdateStrategy,
+) -> (Result<UpdateResult, io::Error>, u32) {
+    let (fetched, attempts) = retry(&fetch.retry, || {
+        git_output_interactive(dir, &["fetch", "--quiet"], fetch.timeout)
+    });
+    let result = fetched.and_then(|_| integrate_upstream(dir, choose_strategy));
+    (result, attempts)
+}
+
+// integrates the fetched upstream into the package repo
+fn integrate_upstream(
+    dir: &Path,
+    choose_strategy: &mut dyn FnMut(&Path, &Divergence) -> UpdateStrategy,
+) -> Result<UpdateResult, io::Error> {
+    let new_upstream = rev_parse(dir, "@{u}")?;
+
+    // the upstream commit HEAD is based on, found in the reflog even after a force-push
+    let old_upstream = match git_output(dir, &["merge-base", "--fork-point", "@{u}", "HEAD"]) {
+        Ok(fork_point) => fork_point.trim().to_string(),
+        Err(_) => git_output(dir, &["merge-base", "@{u}", "HEAD"])?
+            .trim()
+            .to_string(),
+    };
+    // a force-push, which only dropped commits, leaves HEAD ahead of the upstream with commits,
+    // which aren't local, so nothing new upstream isn't enough for up to date
+    let rewritten = !is_ancestor(dir, &old_upstream, &new_upstream)?;
+    if !rewritten && rev_count(dir, "HEAD..@{u}")? == 0 {
+        return Ok(UpdateResult::UpToDate);
+    }
+
+    let divergence = Divergence {
+        dirty: get_status(dir)?.dirty,
+        local_commits: rev_count(dir, &format!("{}..HEAD", old_upstream))?,
+        rewritten,
+    };
+    if divergence.is_clean() {
+        git_output(dir, &["merge", "--ff-only", "--quiet", "@{u}"])?;
+        return Ok(UpdateResult::Updated("fast-forward".to_string()));
+    }
+
+    match choose_strategy(dir, &divergence) {
+        UpdateStrategy::Skip => Ok(UpdateResult::Skipped(divergence)),
+        UpdateStrategy::Reset => {
+            git_output(dir, &["reset", "--hard", "--quiet", "@{u}"])?;
+            Ok(UpdateResult::Updated(format!(
+                "reset hard to the upstream ({})",
+                divergence
+            )))
+        }
+        UpdateStrategy::Rebase => {
+            let onto = [
+                "rebase",
+                "--quiet",
+                "--autostash",
+                "--onto",
+                &new_upstream,
+                &old_upstream,
+            ];
+            if let Err(err) = git_output(dir, &onto) {
+                let _ = git_output(dir, &["rebase", "--abort"]);
+                return Err(io::Error::other(format!(
+                    "rebase failed and was aborted, the repo is unchanged: {}",
+                    err
+                )));
+            }
+            Ok(UpdateResult::Updated(format!(
+                "rebased {} local commit(s) onto the upstream",
+                divergence.local_commits
+            )))
+        }
+        UpdateStrategy::Stash => {
+            if divergence.local_commits > 0 || divergence.rewritten {
+                return Err(io::Error::other(format!(
+                    "can't stash {}, use rebase or reset",
+                    divergence
+                )));
+            }
+            git_output(
+                dir,
+                &["stash", "push", "--quiet", "-m", "aur_helper update"],
+            )?;
+            git_output(dir, &["merge", "--ff-only", "--quiet", "@{u}"])?;
+            if let Err(err) = git_output(dir, &["stash", "pop", "--quiet"]) {
+                return Err(io::Error::other(format!(
+                    "updated, but reapplying the stashed changes conflicts, they are kept in the stash: {}",
+                    err
+                )));
+            }
+            Ok(UpdateResult::Updated(
+                "fast-forward, stashed and reapplied the local changes".to_string(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    pub(crate) fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@localhost"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .expect("Failed to execute git in the test")
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    // creates an 'upstream' work repo pushing to 'remote.git' and returns a clone 'pkg' of it
+    pub(crate) fn prepair_git_repos(tmp_path: &Path) -> PathBuf {
+        let _ = fs::remove_dir_all(tmp_path);
+        let upstream = tmp_path.join("upstream");
+        fs::create_dir_all(&upstream).unwrap();
+        git(tmp_path, &["init", "-q", "--bare", "remote.git"]);
+        git(&upstream, &["init", "-q"]);
+        fs::write(upstream.join("PKGBUILD"), "pkgver=1\n").unwrap();
+        fs::write(upstream.join("README"), "readme\n").unwrap();
+        git(&upstream, &["add", "."]);
+        git(&upstream, &["commit", "-q", "-m", "initial"]);
+        git(&upstream, &["push", "-q", "../remote.git", "HEAD:master"]);
+        git(
+            tmp_path,
+            &["clone", "-q", "-b", "master", "remote.git", "pkg"],
+        );
+        let pkg = tmp_path.join("pkg");
+        git(&pkg, &["config", "user.name", "test"]);
+        git(&pkg, &["config", "user.email", "test@localhost"]);
+        pkg
+    }
+
+    pub(crate) fn push_upstream(tmp_path: &Path, file: &str, content: &str, amend: bool) {
+        let upstream = tmp_path.join("upstream");
+        fs::write(upstream.join(file), content).unwrap();
+        git(&upstream, &["add", "."]);
+        if amend {
+            git(&upstream, &["commit", "-q", "--amend", "-m", "rewritten"]);
+            git(
+                &upstream,
+                &["push", "-q", "-f", "../remote.git", "HEAD:master"],
+            );
+        } else {
+            git(&upstream, &["commit", "-q", "-m", "update"]);
+            git(&upstream, &["push", "-q", "../remote.git", "HEAD:master"]);
+        }
+    }
+
+    #[test]
+    fn update_repo_fast_forward_test() {
+        let tmp_path = Path::new("/tmp/aur_helper_rs_test/update_repo_fast_forward_test");
+        let pkg = prepair_git_repos(tmp_path);
+        let mut never =
+            |_: &Path, _: &Divergence| -> UpdateStrategy { panic!("no strategy needed") };
+
+        assert_eq!(
+            update_repo(&pkg, &FetchOptions::default(), &mut never)
+                .0
+                .unwrap(),
+            UpdateResult::UpToDate
+        );
+        push_upstream(tmp_path, "PKGBUILD", "pkgver=2\n", false);
+        assert!(matches!(
+            update_repo(&pkg, &FetchOptions::default(), &mut never)
+                .0
+                .unwrap(),
+            UpdateResult::Updated(_)
+        ));
+        assert_eq!(
+            fs::read_to_string(pkg.join("PKGBUILD")).unwrap(),
+            "pkgver=2\n"
+        );
+
+        assert!(is_ancestor(&pkg, "HEAD~1", "HEAD").unwrap());
+        assert!(!is_ancestor(&pkg, "HEAD", "HEAD~1").unwrap());
+        // a missing object isn't a rewritten history
+        assert!(is_ancestor(&pkg, &"1".repeat(40), "HEAD").is_err());
+
+        let _ = fs::remove_dir_all(tmp_path);
+    }
+
+    #[test]
+    fn update_repo_strategies_test() {
+        let tmp_path = Path::new("/tmp/aur_helper_rs_test/update_repo_strategies_test");
+
+        // uncommitted changes are stashed and reapplied
+        let pkg = prepair_git_repos(tmp_path);
+        fs::write(pkg.join("README"), "local change\n").unwrap();
+        push_upstream(tmp_path, "PKGBUILD", "pkgver=2\n", false);
+        let mut skip = |_: &Path, div: &Divergence| {
+            assert!(div.dirty);
+            UpdateStrategy::Skip
+        };
+        assert!(matches!(
+            update_repo(&pkg, &FetchOptions::default(), &mut skip)
+                .0
+                .unwrap(),
+            UpdateResult::Skipped(_)
+        ));
+        update_repo(&pkg, &FetchOptions::default(), &mut |_, _| {
+            UpdateStrategy::Stash
+        })
+        .0
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(pkg.join("PKGBUILD")).unwrap(),
+            "pkgver=2\n"
+        );
+        assert_eq!(
+            fs::read_to_string(pkg.join("README")).unwrap(),
+            "local change\n"
+        );
+
+        // local commits are rebased onto the upstream
+        let pkg = prepair_git_repos(tmp_path);
+        fs::write(pkg.join("README"), "local commit\n").unwrap();
+        git(&pkg, &["commit", "-q", "-a", "-m", "local"]);
+        push_upstream(tmp_path, "PKGBUILD", "pkgver=2\n", false);
+        assert!(update_repo(&pkg, &FetchOptions::default(), &mut |_, _| {
+            UpdateStrategy::Stash
+        })
+        .0
+        .is_err());
+        update_repo(&pkg, &FetchOptions::default(), &mut |_, _| {
+            UpdateStrategy::Rebase
+        })
+        .0
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(pkg.join("PKGBUILD")).unwrap(),
+            "pkgver=2\n"
+        );
+        assert_eq!(
+            fs::read_to_string(pkg.join("README")).unwrap(),
+            "local commit\n"
+        );
+
+        // a force-pushed upstream is detected, even after a skipped update, and reset to
+        let pkg = prepair_git_repos(tmp_path);
+        push_upstream(tmp_path, "PKGBUILD", "pkgver=3\n", true);
+        update_repo(&pkg, &FetchOptions::default(), &mut |_, _| {
+            UpdateStrategy::Skip
+        })
+        .0
+        .unwrap();
+        let mut reset = |_: &Path, div: &Divergence| {
+            assert!(div.rewritten);
+            assert_eq!(div.local_commits, 0);
+            UpdateStrategy::Reset
+        };
+        update_repo(&pkg, &FetchOptions::default(), &mut reset)
+            .0
+            .unwrap();
+        assert_eq!(
+            rev_parse(&pkg, "HEAD").unwrap(),
+            rev_parse(&pkg, "@{u}").unwrap()
+        );
+
+        // a force-push, which only dropped the last commit, isn't up to date
+        let pkg = prepair_git_repos(tmp_path);
+        push_upstream(tmp_path, "PKGBUILD", "pkgver=2\n", false);
+        update_repo(&pkg, &FetchOptions::default(), &mut |_, _| {
+            UpdateStrategy::Skip
+        })
+        .0
+        .unwrap();
+        let upstream = tmp_path.join("upstream");
+        git(&upstream, &["reset", "-q", "--hard", "HEAD~1"]);
+        git(
+            &upstream,
+            &["push", "-q", "-f", "../remote.git", "HEAD:master"],
+        );
+        update_repo(&pkg, &FetchOptions::default(), &mut reset)
+            .0
+            .unwrap();
+        assert_eq!(
+            rev_parse(&pkg, "HEAD").unwrap(),
+            rev_parse(&pkg, "@{u}").unwrap()
+        );
+        assert_eq!(
+            fs::read_to_string(pkg.join("PKGBUILD")).unwrap(),
+            "pkgver=1\n"
+        );
+
+        let _ = fs::remove_dir_all(tmp_path);
+    }
+
+    #[test]
+    fn retry_test() {
+        let policy = RetryPolicy {
+            retries: 2,
+            delay: Duration::ZERO,
+        };
+        let network = || {
+            io::Error::other(
+                "git fetch --quiet failed (exit status: 128): fatal: unable to access 'https://aur.archlinux.org/foo.git/': Could not resolve host: aur.archlinux.org",
+            )
+        };
+        let auth = io::Error::other(
+            "git fetch --quiet failed (exit status: 128): remote: HTTP Basic: Access denied\nfatal: Authentication failed for 'https://aur.archlinux.org/foo.git/'",
+        );
+        let merge = io::Error::other(
+            "git merge --ff-only --quiet @{u} failed (exit status: 1): error: connection reset by the merge driver",
+        );
+        assert!(is_transient(&network()));
+        assert!(is_transient(&io::Error::new(
+            io::ErrorKind::TimedOut,
+            "timed out"
+        )));
+        assert!(!is_transient(&auth));
+        assert!(!is_transient(&merge));
+
+        let mut calls = 0;
+        let (result, attempts) = retry(&policy, || {
+            calls += 1;
+            if calls < 3 {
+                Err(network())
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!((result.unwrap(), attempts), (3, 3));
+
+        let (result, attempts) = retry(&policy, || Err::<(), _>(network()));
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+
+        let (result, attempts) =
+            retry(&policy, || Err::<(), _>(io::Error::other(auth.to_string())));
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn parse_status_test() {
+        let clean = parse_status(
+            "# branch.oid 1234\n# branch.head master\n# branch.upstream origin/master\n# branch.ab +0 -0\n",
+        );
+        assert_eq!(clean.to_string(), "clean");
+
+        let diverged = parse_status(
+            "# branch.oid 1234\n# branch.head master\n# branch.upstream origin/master\n# branch.ab +1 -3\n1 .M N... 100644 100644 100644 1234 1234 PKGBUILD\n",
+        );
+        assert!(diverged.dirty);
+        assert_eq!(diverged.to_string(), "dirty, ahead 1, behind 3");
+
+        let detached = parse_status("# branch.oid 1234\n# branch.head (detached)\n");
+        assert_eq!(detached.to_string(), "detached");
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::Config;
+use crate::{get_pkgbase, srcinfo};
+
+// the points, where the user hooks from the config are run for each package
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HookPhase {
+    PreUpdate,
+    PostUpdate,
+    PreBuild,
+    PostBuild,
+    PreInstall,
+    PostInstall,
+}
+
+impl HookPhase {
+    // the key of the hook in the config file
+    pub fn config_key(&self) -> &'static str {
+        match self {
+            HookPhase::PreUpdate => "PreUpdate",
+            HookPhase::PostUpdate => "PostUpdate",
+            HookPhase::PreBuild => "PreBuild",
+            HookPhase::PostBuild => "PostBuild",
+            HookPhase::PreInstall => "PreInstall",
+            HookPhase::PostInstall => "PostInstall",
+        }
+    }
+}
+
+impl fmt::Display for HookPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HookPhase::PreUpdate => "pre-update",
+            HookPhase::PostUpdate => "post-update",
+            HookPhase::PreBuild => "pre-build",
+            HookPhase::PostBuild => "post-build",
+            HookPhase::PreInstall => "pre-install",
+            HookPhase::PostInstall => "post-install",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// runs the global and then the package hooks of the phase with 'sh -c' in the package dir.
+// The package is passed with AUR_HELPER_* environment variables, outcome is the result of the
+// finished phase for post hooks. A non zero exit stops and vetoes the next phase of the package
+pub fn run_hooks(
+    config: &Config,
+    phase: HookPhase,
+    pkg_dir: &Path,
+    outcome: &str,
+) -> Result<(), String> {
+    let pkgbase = get_pkgbase(pkg_dir);
+    let mut hooks = config.get_all(phase.config_key());
+    hooks.append(&mut config.get_pkg_all(&pkgbase, phase.config_key()));
+    if hooks.is_empty() {
+        return Ok(());
+    }
+    let version = srcinfo::read_srcinfo(pkg_dir)
+        .map(|info| info.full_version())
+        .unwrap_or_default();
+
+    for hook in hooks {
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(&hook)
+            .current_dir(pkg_dir)
+            .env("AUR_HELPER_PHASE", phase.to_string())
+            .env("AUR_HELPER_PKG_DIR", pkg_dir)
+            .env("AUR_HELPER_PKGBASE", &pkgbase)
+            .env("AUR_HELPER_VERSION", &version)
+            .env("AUR_HELPER_OUTCOME", outcome)
+            .status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => return Err(format!("{} hook '{}' failed: {}", phase, hook, status)),
+            Err(err) => {
+                return Err(format!(
+                    "{} hook '{}' couldn't be executed: {}",
+                    phase, hook, err
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn run_hooks_test() {
+        let tmp_path = Path::new("/tmp/aur_helper_rs_test/run_hooks_test");
+        let _ = fs::remove_dir_all(tmp_path);
+        let pkg_dir = tmp_path.join("foo");
+        fs::create_dir_all(&pkg_dir).unwrap();
+        fs::write(
+            pkg_dir.join(".SRCINFO"),
+            "pkgbase = foo\n\tpkgver = 1.0\n\tpkgrel = 2\n\npkgname = foo\n",
+        )
+        .unwrap();
+        let config = Config::parse(
+            "[options]
+PostBuild = echo \"$AUR_HELPER_PHASE $AUR_HELPER_PKGBASE $AUR_HELPER_VERSION $AUR_HELPER_OUTCOME\" > hook.log
+[foo]
+PreBuild = exit 3
+",
+        )
+        .unwrap();
+
+        run_hooks(&config, HookPhase::PostBuild, &pkg_dir, "success").unwrap();
+        assert_eq!(
+            fs::read_to_string(pkg_dir.join("hook.log")).unwrap(),
+            "post-build foo 1.0-2 success\n"
+        );
+        let veto = run_hooks(&config, HookPhase::PreBuild, &pkg_dir, "").unwrap_err();
+        assert!(veto.starts_with("pre-build hook 'exit 3' failed"));
+        assert!(run_hooks(&config, HookPhase::PreInstall, &pkg_dir, "").is_ok());
+
+        let _ = fs::remove_dir_all(tmp_path);
+    }
+}
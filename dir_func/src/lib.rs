@@ -1,69 +1,115 @@
 use raur::Raur;
 use std::fs::{DirEntry, ReadDir};
-use std::process::{Command, ExitStatus};
-use std::str::from_utf8;
+use std::process::Command;
 use std::time::{Duration, SystemTime};
-use std::{collections::HashSet, io, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    env, io,
+    path::PathBuf,
+};
 use std::{fs, path::Path};
 
+pub mod chroot;
+pub mod config;
+pub mod date;
+pub mod git;
+pub mod hooks;
+pub mod links;
+pub mod pacman_db;
+pub mod patches;
+pub mod pgp;
+pub mod process;
+pub mod rdeps;
+pub mod sandbox;
+pub mod search;
+pub mod select;
+pub mod srcinfo;
+pub mod state;
+pub mod table;
+pub mod trash;
+pub mod tree;
+pub mod version;
+
+use config::Config;
+use git::{Divergence, UpdateResult, UpdateStrategy};
+use hooks::HookPhase;
+use pacman_db::DbPackage;
+use state::BuildRecord;
+
+// what download_packages_from_git did for a link
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadOutcome {
+    Cloned(PathBuf),
+    // the dir is already a checkout of the link, it can be updated instead
+    Existing(PathBuf),
+    Failed(String),
+    // not cloned or the clone was stopped by Ctrl-C
+    Cancelled,
+}
+
+// clones the git links into current_path, transient network failures are retried. A dir, which is
+// already a checkout of the link, isn't cloned again. After Ctrl-C the remaining links are
+// cancelled. Returns the outcome for each link
 pub fn download_packages_from_git(
     current_path: &Path,
     git_links: Vec<String>,
-) -> Result<Vec<PathBuf>, (Vec<PathBuf>, Vec<String>)> {
-    let mut cloned_pkgs: Vec<PathBuf> = Vec::new();
-    let mut failed_cloned_pkgs: Vec<String> = Vec::new();
-
+    fetch: &git::FetchOptions,
+) -> Vec<(String, DownloadOutcome)> {
+    let mut outcomes: Vec<(String, DownloadOutcome)> = Vec::new();
     for link in git_links {
-        let pkg_name = match link.split("/").last() {
-            Some(name) => {
-                if name.find(".").is_some() {
-                    name.split(".")
-                        .next()
-                        .expect("Should be unreachable, name should have a '.'")
-                } else {
-                    name
-                }
-            }
-            None => {
-                failed_cloned_pkgs.push(link);
-                continue;
-            }
+        let outcome = if process::cancelled() {
+            DownloadOutcome::Cancelled
+        } else {
+            download_package(current_path, &link, fetch)
         };
+        outcomes.push((link, outcome));
+    }
+    outcomes
+}
 
-        let cloned = match Command::new("git")
-            .current_dir(current_path)
-            .arg("clone")
-            .arg(link.clone())
-            .output()
-        {
-            Ok(proc) => proc.status,
-            Err(_) => {
-                failed_cloned_pkgs.push(link);
-                continue;
+fn download_package(current_path: &Path, link: &str, fetch: &git::FetchOptions) -> DownloadOutcome {
+    let pkg_name = match links::dir_name(link) {
+        Some(name) => name,
+        None => return DownloadOutcome::Failed("couldn't get a directory name".to_string()),
+    };
+    let pkg_path = current_path.join(&pkg_name);
+    if pkg_path.exists() {
+        return match git::git_output(&pkg_path, &["remote", "get-url", "origin"]) {
+            Ok(remote) if links::normalize_url(remote.trim()) == links::normalize_url(link) => {
+                DownloadOutcome::Existing(pkg_path)
             }
+            Ok(remote) => DownloadOutcome::Failed(format!(
+                "{} exists and is a checkout of {}",
+                pkg_path.display(),
+                remote.trim()
+            )),
+            Err(_) => DownloadOutcome::Failed(format!(
+                "{} exists and isn't a git checkout",
+                pkg_path.display()
+            )),
         };
-        if cloned.success() {
-            let pkg_path = current_path.join(pkg_name);
-            cloned_pkgs.push(pkg_path);
-        }
     }
-    if failed_cloned_pkgs.is_empty() {
-        return Ok(cloned_pkgs);
-    } else {
-        return Err((cloned_pkgs, failed_cloned_pkgs));
-    }
-}
 
-pub fn remove_uninstalled_dirs(paths: Vec<PathBuf>) -> Option<Command> {
-    let mut rm_cmd = Command::new("rm");
-    rm_cmd.arg("-R").arg("-f");
-    if paths.is_empty() {
-        return None;
-    }
-    for path in paths {
-        rm_cmd.arg(path.to_str().unwrap());
+    // git clone over ssh may ask for the host key or a passphrase
+    let (cloned, attempts) = git::retry(&fetch.retry, || {
+        git::git_output_interactive(
+            current_path,
+            &["clone", "--quiet", "--", link, &pkg_name],
+            fetch.timeout,
+        )
+    });
+    match cloned {
+        Ok(_) => {
+            if attempts > 1 {
+                println!("{} cloned after {} attempts", link, attempts);
+            }
+            DownloadOutcome::Cloned(pkg_path)
+        }
+        Err(_) if process::cancelled() => DownloadOutcome::Cancelled,
+        Err(err) => {
+            DownloadOutcome::Failed(format!("failed after {} attempt(s): {}", attempts, err))
+        }
     }
-    Some(rm_cmd)
 }
 
 pub fn check_installed(paths: Vec<PathBuf>) -> Result<Vec<PathBuf>, io::Error> {
@@ -85,39 +131,203 @@ pub fn check_installed(paths: Vec<PathBuf>) -> Result<Vec<PathBuf>, io::Error> {
     Ok(found_pgks)
 }
 
-pub fn install_packages(dirs: Vec<PathBuf>) -> Result<Command, (Command, Vec<PathBuf>)> {
-    let mut failed_packges: Vec<PathBuf> = Vec::new();
-    let mut packages: Vec<PathBuf> = Vec::new();
+// options for install_packages, which aren't part of the config file
+#[derive(Debug, Clone)]
+pub struct InstallOptions {
+    // the pacman database with the installed versions
+    pub db_path: PathBuf,
+    // install the packages even if the same version is already installed
+    pub reinstall: bool,
+}
+
+impl Default for InstallOptions {
+    fn default() -> Self {
+        InstallOptions {
+            db_path: PathBuf::from(pacman_db::PACMAN_DB_PATH),
+            reinstall: false,
+        }
+    }
+}
+
+// a build package-file with its version and the installed version, None if not installed
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstallEntry {
+    pub dir: PathBuf,
+    pub file: PathBuf,
+    pub name: String,
+    pub version: String,
+    pub installed: Option<String>,
+}
+
+impl InstallEntry {
+    // the same version is installed, compared like pacman, so 0:1.0-1 equals 1.0-1
+    pub fn is_unchanged(&self) -> bool {
+        self.installed
+            .as_ref()
+            .is_some_and(|installed| version::vercmp(&self.version, installed).is_eq())
+    }
+
+    pub fn is_downgrade(&self) -> bool {
+        self.installed
+            .as_ref()
+            .is_some_and(|installed| version::vercmp(&self.version, installed).is_lt())
+    }
+}
+
+// the outcome of install_packages: the packages to install, the ones already installed in the same
+// version and the dirs without a package or vetoed by a pre-install hook
+#[derive(Debug, Default)]
+pub struct InstallPlan {
+    pub install: Vec<InstallEntry>,
+    pub unchanged: Vec<InstallEntry>,
+    pub failed: Vec<PathBuf>,
+}
+
+impl InstallPlan {
+    // the dirs of the packages to install
+    pub fn dirs(&self) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for entry in &self.install {
+            if !dirs.contains(&entry.dir) {
+                dirs.push(entry.dir.clone());
+            }
+        }
+        dirs
+    }
+
+    // generates the pacman command for the packages to install, None if there is nothing to
+    // install, CALLS SUDO!
+    pub fn command(&self) -> Option<Command> {
+        if self.install.is_empty() {
+            return None;
+        }
+        let mut inst_cmd = Command::new("sudo");
+        inst_cmd.arg("pacman");
+        inst_cmd.arg("-U");
+        for entry in &self.install {
+            inst_cmd.arg(&entry.file);
+        }
+        Some(inst_cmd)
+    }
+}
+
+// finds the latest build packages of the dirs and compares them with the installed versions,
+// packages already installed in the same version are only installed with options.reinstall
+pub fn install_packages(
+    dirs: Vec<PathBuf>,
+    config: &Config,
+    options: &InstallOptions,
+) -> InstallPlan {
+    let installed: HashMap<String, String> = match pacman_db::read_local_db(&options.db_path) {
+        Ok(packages) => packages
+            .into_iter()
+            .map(|package| (package.name, package.version))
+            .collect(),
+        Err(err) => {
+            println!(
+                "WARNING: couldn't read the installed packages, installing every package, error: {}",
+                err
+            );
+            HashMap::new()
+        }
+    };
+    let mut plan = InstallPlan::default();
     for dir in dirs {
-        match fs::read_dir(dir.clone()) {
-            Ok(read_dir) => {
-                let files = get_latest_build_package(read_dir);
-                match files {
-                    Ok(path_buf) => {
-                        packages.push(path_buf);
-                    }
+        if let Err(err) = hooks::run_hooks(config, HookPhase::PreInstall, &dir, "") {
+            println!("WARNING: not installing {}, {}", dir.display(), err);
+            plan.failed.push(dir);
+            continue;
+        }
+        let requested = state::read_requested(&dir);
+        let files = if requested.is_empty() {
+            match fs::read_dir(dir.clone()) {
+                Ok(read_dir) => match get_latest_build_package(read_dir) {
+                    Ok(file) => vec![file],
                     Err(e) => {
                         println!("WARNING: couldn't find a build package, error: {}", e);
-                        failed_packges.push(dir.to_path_buf());
+                        plan.failed.push(dir);
+                        continue;
                     }
+                },
+                Err(_) => {
+                    plan.failed.push(dir);
+                    continue;
                 }
             }
-            Err(_) => {
-                failed_packges.push(dir.to_path_buf());
+        } else {
+            // only the packages of a split package the user asked for
+            let files = get_requested_packages(&dir, &requested);
+            if files.is_empty() {
+                println!(
+                    "WARNING: couldn't find a build package of {} in {}",
+                    requested.join(", "),
+                    dir.display()
+                );
+                plan.failed.push(dir);
+                continue;
+            }
+            files
+        };
+        for file in files {
+            let file_name = file
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let (name, version) = version::parse_artifact_name(&file_name)
+                .unwrap_or_else(|| (file_name, "unknown".to_string()));
+            let entry = InstallEntry {
+                installed: installed.get(&name).cloned(),
+                dir: dir.clone(),
+                file,
+                name,
+                version,
+            };
+            if !options.reinstall && entry.is_unchanged() {
+                plan.unchanged.push(entry);
+            } else {
+                plan.install.push(entry);
             }
         }
     }
-    let mut inst_cmd = Command::new("sudo");
-    inst_cmd.arg("pacman");
-    inst_cmd.arg("-U");
-    for package in packages {
-        inst_cmd.arg(package.to_str().expect("Couldn't convert path to string"));
-    }
-    if failed_packges.is_empty() {
-        Ok(inst_cmd)
-    } else {
-        Err((inst_cmd, failed_packges))
+    plan
+}
+
+// the newest package-file of each requested package in the dir, requested packages without one are
+// left out
+pub fn get_requested_packages(dir: &Path, requested: &[String]) -> Vec<PathBuf> {
+    let mut newest: Vec<(String, SystemTime, PathBuf)> = Vec::new();
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    for entry in read_dir.filter_map(|entry| entry.ok()) {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some((name, _)) = version::parse_artifact_name(&file_name) else {
+            continue;
+        };
+        if !requested.contains(&name) {
+            continue;
+        }
+        let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) else {
+            continue;
+        };
+        match newest
+            .iter_mut()
+            .find(|(newest_name, _, _)| newest_name == &name)
+        {
+            Some(found) if found.1 >= modified => {}
+            Some(found) => *found = (name, modified, entry.path()),
+            None => newest.push((name, modified, entry.path())),
+        }
     }
+    requested
+        .iter()
+        .filter_map(|name| {
+            newest
+                .iter()
+                .find(|(newest_name, _, _)| newest_name == name)
+                .map(|(_, _, path)| path.clone())
+        })
+        .collect()
 }
 
 // finds the build package-files in a directory and fails, if not file was found
@@ -133,21 +343,18 @@ pub fn get_latest_build_package(dir: ReadDir) -> Result<PathBuf, io::Error> {
                 .into_string()
                 .expect("Failed to convert from OsString to String");
             let file_name_slice: Vec<&str> = file_name.split(".").collect();
-            match file_name_slice.last() {
-                Some(last) => {
-                    if last == &"zst" {
-                        possible_packages.push(file);
-                    }
+            if let Some(last) = file_name_slice.last() {
+                if last == &"zst" {
+                    possible_packages.push(file);
                 }
-                None => {}
             }
         }
     }
     if possible_packages.is_empty() {
-        return Err(io::Error::new(
+        Err(io::Error::new(
             io::ErrorKind::NotFound,
             "Couldn't find a build package-file",
-        ));
+        ))
     } else {
         // TODO: don't depend on metada, depend on pkg version name
         let mut min: DirEntry = possible_packages.pop().unwrap();
@@ -159,58 +366,489 @@ pub fn get_latest_build_package(dir: ReadDir) -> Result<PathBuf, io::Error> {
                 min_modified = file_modifed;
             };
         }
-        return Ok(min.path());
+        Ok(min.path())
+    }
+}
+
+// options for build_packages, which aren't part of the config file
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    // additional makepkg arguments for every package, after the ones from the config
+    pub makepkg_args: Vec<String>,
+    // build even if the packages of the current version are already built
+    pub rebuild: bool,
+    // build every package in the bubblewrap sandbox, not only the ones enabled in the config
+    pub sandbox: bool,
+    // build every package in a fresh copy of the clean build root in the state dir
+    pub chroot: bool,
+}
+
+// the outcome of build_packages: built, skipped because already built, failed with the reason and
+// cancelled by Ctrl-C
+#[derive(Debug, Clone, Default)]
+pub struct BuildResults {
+    pub built: Vec<PathBuf>,
+    pub skipped: Vec<PathBuf>,
+    pub failed: Vec<(PathBuf, String)>,
+    pub cancelled: Vec<PathBuf>,
+}
+
+// the package extension of makepkg: $PKGEXT, the makepkg.conf of the user or the system one
+pub fn get_pkgext() -> String {
+    if let Ok(pkgext) = env::var("PKGEXT") {
+        return pkgext;
+    }
+    let mut confs: Vec<PathBuf> = vec![PathBuf::from("/etc/makepkg.conf")];
+    if let Some(home) = env::var_os("HOME") {
+        confs.push(Path::new(&home).join(".config/pacman/makepkg.conf"));
+        confs.push(Path::new(&home).join(".makepkg.conf"));
+    }
+    let mut pkgext = ".pkg.tar.zst".to_string();
+    for conf in confs {
+        let Ok(content) = fs::read_to_string(conf) else {
+            continue;
+        };
+        for line in content.lines() {
+            if let Some(value) = line.trim().strip_prefix("PKGEXT=") {
+                pkgext = value.trim_matches(|c| c == '\'' || c == '"').to_string();
+            }
+        }
+    }
+    pkgext
+}
+
+// checks, if all packages of the .SRCINFO version are built after the current git HEAD was pulled
+pub fn is_build_current(dir: &Path, pkgext: &str) -> bool {
+    let Ok(info) = srcinfo::read_srcinfo(dir) else {
+        return false;
+    };
+    let Ok(head_changed) = git::head_integrated_time(dir) else {
+        return false;
+    };
+    let artifacts = info.artifact_names(pkgext);
+    !artifacts.is_empty()
+        && artifacts.iter().all(|artifact| {
+            fs::metadata(dir.join(artifact))
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .is_some_and(|modified| modified.as_secs() >= head_changed)
+        })
+}
+
+// the makepkg arguments for the package: -s, the global and package 'MakepkgArgs' of the config
+// and the ones of the build options
+pub fn get_makepkg_args(dir: &Path, config: &Config, options: &BuildOptions) -> Vec<String> {
+    let pkgbase = get_pkgbase(dir);
+    let mut args: Vec<String> = vec!["-s".to_string()];
+    for config_args in config
+        .get_all("MakepkgArgs")
+        .into_iter()
+        .chain(config.get_pkg_all(&pkgbase, "MakepkgArgs"))
+    {
+        args.extend(config_args.split_whitespace().map(|arg| arg.to_string()));
     }
+    args.extend(options.makepkg_args.iter().cloned());
+    args
 }
 
-// build all packages in dir, returns build packages of on err the failed packages and the status
-pub fn build_packages(dirs: Vec<PathBuf>) -> Result<Vec<PathBuf>, Vec<(PathBuf, ExitStatus)>> {
-    let mut failed_dirs: Vec<(PathBuf, ExitStatus)> = Vec::new();
+// the package is built in the sandbox, if set by the options or 'Sandbox = true' in the config,
+// the package section overwrites the global one
+pub fn use_sandbox(dir: &Path, config: &Config, options: &BuildOptions) -> bool {
+    if options.sandbox {
+        return true;
+    }
+    let pkg_sandbox = config.get_pkg_all(&get_pkgbase(dir), "Sandbox").pop();
+    pkg_sandbox.as_deref().or(config.get("Sandbox")) == Some("true")
+}
+
+// build all packages in dir, the user patches of a package are applied before and reverted after
+// the build, returns the built, the skipped (already built) and the failed packages with the reason.
+// A failing pre-build hook skips the build, a failing post-build hook marks the build as failed
+pub fn build_packages(dirs: Vec<PathBuf>, config: &Config, options: &BuildOptions) -> BuildResults {
+    let mut failed_dirs: Vec<(PathBuf, String)> = Vec::new();
     let mut success_dirs: Vec<PathBuf> = Vec::new();
+    let mut skipped_dirs: Vec<PathBuf> = Vec::new();
+    let mut cancelled_dirs: Vec<PathBuf> = Vec::new();
+    let pkgext = get_pkgext();
+    let timeout = config.timeout("BuildTimeout", None);
+    // the build roots are created or updated once, before the first chroot build in them
+    let mut chroot_roots: HashMap<PathBuf, Result<(), String>> = HashMap::new();
     for dir in dirs {
-        let status = Command::new("makepkg")
-            .arg("-s")
-            .current_dir(dir.clone())
-            .status()
-            .expect("Failed to execute makepkg");
-        if status.success() {
+        if process::cancelled() {
+            cancelled_dirs.push(dir);
+            continue;
+        }
+        if !options.rebuild && is_build_current(&dir, &pkgext) {
+            println!("{}: already built, skipping", dir.display());
+            skipped_dirs.push(dir);
+            continue;
+        }
+        if let Err(err) = hooks::run_hooks(config, HookPhase::PreBuild, &dir, "") {
+            println!("{}: skipping the build, {}", dir.display(), err);
+            failed_dirs.push((dir.clone(), err));
+            continue;
+        }
+        let patch_files = match patches::find_patches(&dir) {
+            Ok(patch_files) => patch_files,
+            Err(err) => {
+                let reason = format!("couldn't read the patches: {}", err);
+                record_build(&dir, false, &reason, &[], Vec::new());
+                failed_dirs.push((dir.clone(), reason));
+                continue;
+            }
+        };
+        let snapshot = if patch_files.is_empty() {
+            None
+        } else {
+            match patches::apply_patches(&dir, &patch_files) {
+                Ok(snapshot) => {
+                    println!("{}: applied {} patch(es)", dir.display(), patch_files.len());
+                    snapshot
+                }
+                Err(err) => {
+                    println!("{}: skipping the build, {}", dir.display(), err);
+                    record_build(&dir, false, &err, &[], Vec::new());
+                    failed_dirs.push((dir.clone(), err));
+                    continue;
+                }
+            }
+        };
+
+        let makepkg_args = get_makepkg_args(&dir, config, options);
+        let mut commands: Vec<String> = Vec::new();
+        let build = if options.chroot {
+            let aur_path = state::aur_path_of(&dir).to_path_buf();
+            let root = chroot_roots
+                .entry(aur_path.clone())
+                .or_insert_with(|| chroot::prepare_root(&aur_path));
+            match root {
+                Ok(()) => chroot::makepkg(&dir, &makepkg_args, timeout, &mut commands),
+                Err(err) => Err(format!("the build root isn't available: {}", err)),
+            }
+        } else if use_sandbox(&dir, config, options) {
+            println!("{}: building in the sandbox", dir.display());
+            sandbox::makepkg(&dir, &makepkg_args, timeout, &mut commands)
+        } else {
+            let mut makepkg = Command::new("makepkg");
+            makepkg.args(&makepkg_args).current_dir(dir.clone());
+            commands.push(process::command_line(&makepkg));
+            process::run_status(&mut makepkg, timeout).map_err(|err| format!("makepkg: {}", err))
+        };
+
+        if !patch_files.is_empty() {
+            if let Err(err) = patches::revert_patches(&dir, snapshot) {
+                println!(
+                    "WARNING: couldn't revert the patches of {}: {}",
+                    dir.display(),
+                    err
+                );
+            }
+        }
+        if process::cancelled() {
+            println!("{}: build cancelled", dir.display());
+            record_build(&dir, false, "cancelled", &makepkg_args, commands);
+            cancelled_dirs.push(dir);
+            continue;
+        }
+        let (success, status) = match build {
+            Ok(status) => (status.success(), status.to_string()),
+            Err(err) => {
+                println!("{}: {}", dir.display(), err);
+                (false, err)
+            }
+        };
+        record_build(&dir, success, &status, &makepkg_args, commands);
+        let outcome = if success {
+            "success".to_string()
+        } else {
+            format!("failed: {}", status)
+        };
+        if let Err(err) = hooks::run_hooks(config, HookPhase::PostBuild, &dir, &outcome) {
+            println!("{}: {}", dir.display(), err);
+            failed_dirs.push((dir.clone(), err));
+        } else if success {
             success_dirs.push(dir.clone());
         } else {
             failed_dirs.push((dir.clone(), status));
         }
     }
-    if failed_dirs.is_empty() {
-        return Ok(success_dirs);
+    BuildResults {
+        built: success_dirs,
+        skipped: skipped_dirs,
+        failed: failed_dirs,
+        cancelled: cancelled_dirs,
     }
-    Err(failed_dirs)
 }
 
-// goes through the directories and calls 'git pull' and returns a tuple vector of successful updated
-// dirs and on fail a vector of the failed dirs and their  the exit status
-pub fn update_packages(dirs: Vec<PathBuf>) -> Result<Vec<PathBuf>, Vec<(PathBuf, ExitStatus)>> {
-    let mut failed_dirs: Vec<(PathBuf, ExitStatus)> = Vec::new();
-    let mut true_success_dirs: Vec<PathBuf> = Vec::new();
-    for dir in dirs {
-        let output = Command::new("git")
-            .arg("pull")
-            .current_dir(dir.clone())
-            .output()
-            .expect("Failed to execute git pull!");
+fn record_build(
+    dir: &Path,
+    success: bool,
+    status: &str,
+    makepkg_args: &[String],
+    commands: Vec<String>,
+) {
+    let pgp_keys = srcinfo::read_srcinfo(dir)
+        .map(|info| info.validpgpkeys)
+        .unwrap_or_default();
+    let record = BuildRecord {
+        time: state::now_secs(),
+        success,
+        status: status.to_string(),
+        makepkg_args: makepkg_args.to_vec(),
+        commands,
+        pgp_key_files: pgp::key_file_hashes(dir, &pgp_keys),
+        pgp_keys,
+    };
+    if let Err(err) = state::write_build_record(dir, &record) {
+        println!(
+            "WARNING: couldn't record the build result of {}: {}",
+            dir.display(),
+            err
+        );
+    }
+}
 
-        if output.status.success() {
-            if !(from_utf8(output.stdout.as_slice()).unwrap() == "Already up to date.\n") {
-                true_success_dirs.push(dir.clone());
-                println!("{} updated!", dir.display());
-            }
-            println!("{} up to date!", dir.display());
+// the default timeout of git fetch for an update, overwritten by UpdateTimeout in the config
+pub const DEFAULT_UPDATE_TIMEOUT: Duration = Duration::from_secs(300);
+
+// retries of a clone or fetch after a network failure, the delay doubles after each attempt
+pub const DEFAULT_GIT_RETRIES: u32 = 2;
+pub const DEFAULT_GIT_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+// the fetch settings from the config: UpdateTimeout, GitRetries and GitRetryDelay
+pub fn fetch_options(config: &Config) -> git::FetchOptions {
+    let retries = match config.get("GitRetries").map(|retries| retries.parse()) {
+        Some(Ok(retries)) => retries,
+        Some(Err(_)) => {
+            println!("WARNING: GitRetries isn't a number, using the default");
+            DEFAULT_GIT_RETRIES
+        }
+        None => DEFAULT_GIT_RETRIES,
+    };
+    git::FetchOptions {
+        timeout: config.timeout("UpdateTimeout", Some(DEFAULT_UPDATE_TIMEOUT)),
+        retry: git::RetryPolicy {
+            retries,
+            delay: config
+                .timeout("GitRetryDelay", Some(DEFAULT_GIT_RETRY_DELAY))
+                .unwrap_or_default(),
+        },
+    }
+}
+
+// the outcome of update_packages: updated, failed with the reason and cancelled by Ctrl-C
+#[derive(Debug, Clone, Default)]
+pub struct UpdateResults {
+    pub updated: Vec<PathBuf>,
+    pub failed: Vec<(PathBuf, String)>,
+    pub cancelled: Vec<PathBuf>,
+}
+
+// goes through the directories, fetches and fast-forwards them and returns the updated dirs, dirs
+// which can't be fast-forwarded are handled by the strategy from choose_strategy.
+// A failing pre-update hook skips the update, a failing post-update hook vetoes the build.
+// A fetch is killed after the UpdateTimeout, Ctrl-C cancels the remaining packages
+pub fn update_packages(
+    dirs: Vec<PathBuf>,
+    config: &Config,
+    choose_strategy: &mut dyn FnMut(&Path, &Divergence) -> UpdateStrategy,
+) -> UpdateResults {
+    let mut results = UpdateResults::default();
+    let fetch = fetch_options(config);
+    for dir in dirs {
+        if process::cancelled() {
+            results.cancelled.push(dir);
+            continue;
+        }
+        if let Err(err) = hooks::run_hooks(config, HookPhase::PreUpdate, &dir, "") {
+            println!("{} not updated! ({})", dir.display(), err);
+            results.failed.push((dir.clone(), err));
+            continue;
+        }
+        let (result, attempts) = git::update_repo(&dir, &fetch, choose_strategy);
+        let attempts_info = if attempts > 1 {
+            format!(", {} attempts", attempts)
         } else {
-            failed_dirs.push((dir.clone(), output.status));
+            String::new()
+        };
+        let (updated, outcome) = match result {
+            Ok(UpdateResult::UpToDate) => {
+                println!("{} up to date!{}", dir.display(), attempts_info);
+                (false, "up-to-date".to_string())
+            }
+            Ok(UpdateResult::Updated(how)) => {
+                println!("{} updated! ({}{})", dir.display(), how, attempts_info);
+                (true, "updated".to_string())
+            }
+            Ok(UpdateResult::Skipped(divergence)) => {
+                println!(
+                    "{} skipped! ({}{})",
+                    dir.display(),
+                    divergence,
+                    attempts_info
+                );
+                (false, "skipped".to_string())
+            }
+            Err(_) if process::cancelled() => {
+                println!("{} cancelled!", dir.display());
+                results.cancelled.push(dir);
+                continue;
+            }
+            Err(err) => {
+                println!(
+                    "{} failed after {} attempt(s)! ({})",
+                    dir.display(),
+                    attempts,
+                    err
+                );
+                results.failed.push((dir.clone(), err.to_string()));
+                (false, format!("failed: {}", err))
+            }
+        };
+        match hooks::run_hooks(config, HookPhase::PostUpdate, &dir, &outcome) {
+            Ok(()) if updated => results.updated.push(dir.clone()),
+            Ok(()) => {}
+            Err(err) => {
+                println!("{}: {}", dir.display(), err);
+                if updated {
+                    results.failed.push((dir.clone(), err));
+                }
+            }
+        }
+    }
+    results
+}
+
+// returns the package names build by the package dir, falls back to the dir name without a .SRCINFO
+pub fn get_pkgnames(dir: &Path) -> Vec<String> {
+    match srcinfo::read_srcinfo(dir) {
+        Ok(info) if !info.packages.is_empty() => info.pkgnames(),
+        _ => dir
+            .file_name()
+            .map(|name| vec![name.to_string_lossy().to_string()])
+            .unwrap_or_default(),
+    }
+}
+
+// the pkgbase of the package dir, falls back to the dir name
+pub fn get_pkgbase(pkg_dir: &Path) -> String {
+    match srcinfo::read_srcinfo(pkg_dir) {
+        Ok(info) => info.pkgbase,
+        Err(_) => pkg_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+// a package dir with packages available in the sync databases of pacman
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoMove {
+    pub dir: PathBuf,
+    // the repo packages shadowing packages of the dir
+    pub repo_pkgs: Vec<DbPackage>,
+    // the pkgnames of the dir, which are still only in the AUR
+    pub aur_only: Vec<String>,
+}
+
+impl RepoMove {
+    // all packages of the pkgbase moved, the dir isn't needed anymore
+    pub fn complete(&self) -> bool {
+        self.aur_only.is_empty()
+    }
+}
+
+// finds the packages of the dirs, which are available in the sync databases of pacman, returns the
+// dirs with at least one of their packages in the repos
+pub fn find_repo_packages(dirs: Vec<PathBuf>, db_path: &Path) -> Result<Vec<RepoMove>, io::Error> {
+    let sync_pkgs = pacman_db::read_sync_dbs(db_path)?;
+    let mut found: Vec<RepoMove> = Vec::new();
+    for dir in dirs {
+        let pkgnames = get_pkgnames(&dir);
+        let repo_pkgs: Vec<DbPackage> = sync_pkgs
+            .iter()
+            .filter(|pkg| pkgnames.contains(&pkg.name))
+            .cloned()
+            .collect();
+        if repo_pkgs.is_empty() {
+            continue;
         }
+        let aur_only: Vec<String> = pkgnames
+            .into_iter()
+            .filter(|name| !repo_pkgs.iter().any(|pkg| &pkg.name == name))
+            .collect();
+        found.push(RepoMove {
+            dir,
+            repo_pkgs,
+            aur_only,
+        });
+    }
+    Ok(found)
+}
+
+// generates the pacman command to install the given packages from the repos, CALLS SUDO!
+pub fn repo_install_cmd(pkg_names: Vec<String>) -> Option<Command> {
+    if pkg_names.is_empty() {
+        return None;
     }
-    if failed_dirs.is_empty() {
-        return Ok(true_success_dirs);
+    let mut inst_cmd = Command::new("sudo");
+    inst_cmd.arg("pacman").arg("-S");
+    for name in pkg_names {
+        inst_cmd.arg(name);
+    }
+    Some(inst_cmd)
+}
+
+// generates the pacman command to remove the packages with the dependencies not needed anymore,
+// CALLS SUDO!
+pub fn remove_packages_cmd(pkg_names: &[String], db_path: &Path) -> Option<Command> {
+    if pkg_names.is_empty() {
+        return None;
+    }
+    let mut rm_cmd = Command::new("sudo");
+    rm_cmd
+        .arg("pacman")
+        .arg("-Rns")
+        .arg("--dbpath")
+        .arg(db_path)
+        .arg("--")
+        .args(pkg_names);
+    Some(rm_cmd)
+}
+
+// the packages pacman -Rns would remove for the packages, the packages themselves included
+pub fn removal_targets(pkg_names: &[String], db_path: &Path) -> Result<Vec<String>, io::Error> {
+    let output = Command::new("pacman")
+        .arg("-Rns")
+        .arg("--print")
+        .arg("--print-format")
+        .arg("%n")
+        .arg("--dbpath")
+        .arg(db_path)
+        .arg("--")
+        .args(pkg_names)
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
     }
-    Err(failed_dirs)
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+// generates the repo-remove command to remove the packages from the local repository database
+pub fn repo_remove_cmd(repo_db: &Path, pkg_names: &[String]) -> Option<Command> {
+    if pkg_names.is_empty() {
+        return None;
+    }
+    let mut rm_cmd = Command::new("repo-remove");
+    rm_cmd.arg(repo_db).args(pkg_names);
+    Some(rm_cmd)
 }
 
 // returns the directories in path and warns if it's a wrong directory
@@ -226,9 +864,16 @@ pub fn get_dirs(current_path: &Path, warn_wrong_dir: bool) -> Result<Vec<PathBuf
     for path in fs::read_dir(current_path).expect("Couldn't read path!") {
         let dir_ent = path.expect("Dir-entry error!").path();
 
+        // hidden dirs like the state dir of aur_helper aren't packages
+        if dir_ent
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+
         if warn_wrong_dir && !dir_ent.is_dir() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
+            return Err(io::Error::other(
                 "AUR Path contains not only directorys, right AUR-dir?",
             ));
         }
@@ -238,59 +883,245 @@ pub fn get_dirs(current_path: &Path, warn_wrong_dir: bool) -> Result<Vec<PathBuf
     Ok(paths)
 }
 
-pub fn print_detailed_pkg_info(pkg: raur::Package) {
-    // ------ calculate time
-    let last_mod = Duration::new(pkg.last_modified.unsigned_abs(), 0);
-    let last_mod = SystemTime::now() - last_mod;
-    // last_mod is SystemTime, convert SystemTime to unix time
-    let last_mod = last_mod
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .expect("Negative result on duration calculation");
+// everything known about a package dir, collected without changing anything
+#[derive(Debug, Clone, Default)]
+pub struct PackageStatus {
+    pub dir: PathBuf,
+    pub pkgbase: String,
+    pub pkgnames: Vec<String>,
+    pub git: Option<git::GitStatus>,
+    pub local_version: Option<String>,
+    pub installed_version: Option<String>,
+    pub latest_build: Option<PathBuf>,
+    pub aur_version: Option<String>,
+    pub ignored: bool,
+    pub held: bool,
+    pub last_build: Option<BuildRecord>,
+}
 
-    let days = last_mod.as_secs() / (60 * 60 * 24);
+// collects the status of the package dirs from git, the .SRCINFO, the pacman db and conf and the
+// last build, the AUR version is filled by get_aur_versions
+pub fn get_package_status(
+    dirs: Vec<PathBuf>,
+    db_path: &Path,
+    pacman_conf: &Path,
+) -> Vec<PackageStatus> {
+    let installed: Vec<DbPackage> = pacman_db::read_local_db(db_path).unwrap_or_else(|err| {
+        println!("WARNING: couldn't read the local pacman database: {}", err);
+        Vec::new()
+    });
+    let ignored = pacman_db::read_conf_option(pacman_conf, "IgnorePkg").unwrap_or_default();
+    let held = pacman_db::read_conf_option(pacman_conf, "HoldPkg").unwrap_or_default();
 
-    let last_mod = match days {
-        0 => format!("today"),
-        1 => format!("before {} day", days),
-        2..=31 => format!("before {} days", days),
-        32..=365 => format!("before {} months", (days / 31)),
-        _ => format!("before {} years", (days / 365)),
-    };
+    let mut status_vec: Vec<PackageStatus> = Vec::new();
+    for dir in dirs {
+        let srcinfo = srcinfo::read_srcinfo(&dir).ok();
+        let pkgnames = get_pkgnames(&dir);
+        let pkgbase = match &srcinfo {
+            Some(info) => info.pkgbase.clone(),
+            None => pkgnames.first().cloned().unwrap_or_default(),
+        };
+        let installed_version = installed
+            .iter()
+            .find(|pkg| pkgnames.contains(&pkg.name))
+            .map(|pkg| pkg.version.clone());
+        let latest_build = fs::read_dir(&dir)
+            .ok()
+            .and_then(|read_dir| get_latest_build_package(read_dir).ok());
+
+        status_vec.push(PackageStatus {
+            git: git::get_status(&dir).ok(),
+            local_version: srcinfo.map(|info| info.full_version()),
+            installed_version,
+            latest_build,
+            aur_version: None,
+            ignored: pkgnames.iter().any(|name| ignored.contains(name)),
+            held: pkgnames.iter().any(|name| held.contains(name)),
+            last_build: state::read_build_record(&dir),
+            pkgbase,
+            pkgnames,
+            dir,
+        });
+    }
+    status_vec
+}
+
+// fetches the AUR versions of the packages with one info request, keyed by the package base
+pub async fn get_aur_versions(
+    pkgnames: Vec<String>,
+) -> Result<HashMap<String, String>, raur::Error> {
+    let raur_handler = raur::Handle::new();
+    let pkgs = raur_handler.info(&pkgnames).await?;
+    Ok(pkgs
+        .into_iter()
+        .map(|pkg| (pkg.package_base, pkg.version))
+        .collect())
+}
+
+// the requested AUR packages grouped by their package base, in the order of the names, with the
+// requested names of each base. Names not in the AUR are returned separately
+pub fn group_by_pkgbase(
+    names: &[String],
+    pkgs: &[raur::Package],
+) -> (Vec<(String, Vec<String>)>, Vec<String>) {
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    let mut not_found: Vec<String> = Vec::new();
+    for name in names {
+        let Some(pkg) = pkgs.iter().find(|pkg| &pkg.name == name) else {
+            if !not_found.contains(name) {
+                not_found.push(name.clone());
+            }
+            continue;
+        };
+        match groups
+            .iter_mut()
+            .find(|(pkgbase, _)| pkgbase == &pkg.package_base)
+        {
+            Some((_, requested)) if requested.contains(name) => {}
+            Some((_, requested)) => requested.push(name.clone()),
+            None => groups.push((pkg.package_base.clone(), vec![name.clone()])),
+        }
+    }
+    (groups, not_found)
+}
+
+// looks up the package names with one info request and groups them by package base, split packages
+// like foo-headers are cloned from the repo of their base
+pub async fn resolve_pkgbases(
+    names: &[String],
+) -> Result<(Vec<(String, Vec<String>)>, Vec<String>), raur::Error> {
+    let raur_handler = raur::Handle::new();
+    let pkgs = raur_handler.info(names).await?;
+    Ok(group_by_pkgbase(names, &pkgs))
+}
+
+// the age and the formatted date of a unix timestamp, e.g. "3 days ago (2024-05-01)"
+pub fn format_timestamp(secs: i64, date_format: &str) -> String {
+    let age = state::now_secs().saturating_sub(secs.max(0) as u64);
+    format!(
+        "{} ({})",
+        date::format_age(age),
+        date::format_date(secs, date_format)
+    )
+}
+
+// where a dependency comes from, marked in the package details
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepSource {
+    Installed,
+    Repo,
+    Aur,
+}
+
+impl DepSource {
+    pub fn mark(&self) -> char {
+        match self {
+            DepSource::Installed => 'i',
+            DepSource::Repo => 'r',
+            DepSource::Aur => 'a',
+        }
+    }
+}
+
+// the installed and the repo packages to find the source of the dependencies
+#[derive(Debug, Clone, Default)]
+pub struct DepSources {
+    pub installed: Vec<DbPackage>,
+    pub repo: Vec<DbPackage>,
+}
+
+impl DepSources {
+    pub fn load(db_path: &Path) -> Self {
+        let installed = pacman_db::read_local_db(db_path).unwrap_or_else(|err| {
+            println!("WARNING: couldn't read the local pacman database: {}", err);
+            Vec::new()
+        });
+        let repo = pacman_db::read_sync_dbs(db_path).unwrap_or_else(|err| {
+            println!("WARNING: couldn't read the sync databases: {}", err);
+            Vec::new()
+        });
+        DepSources { installed, repo }
+    }
+
+    // installed, in a repo or else only in the AUR, a dependency like foo>=1.0 is satisfied by a
+    // package named or providing foo
+    pub fn source(&self, dep: &str) -> DepSource {
+        let name = srcinfo::dep_name(dep);
+        let satisfies = |pkg: &&DbPackage| {
+            pkg.name == name
+                || pkg
+                    .provides
+                    .iter()
+                    .any(|provide| srcinfo::dep_name(provide) == name)
+        };
+        if self.installed.iter().any(|pkg| satisfies(&pkg)) {
+            DepSource::Installed
+        } else if self.repo.iter().any(|pkg| satisfies(&pkg)) {
+            DepSource::Repo
+        } else {
+            DepSource::Aur
+        }
+    }
+}
 
-    // ------ format print
+// how print_detailed_pkg_info shows a package
+#[derive(Debug, Clone, Default)]
+pub struct InfoOptions {
+    pub date_format: String,
+    // the width of the dependency table, None for no limit
+    pub width: Option<usize>,
+    pub overflow: table::Overflow,
+    pub deps: DepSources,
+}
+
+// prints all details of a package, the dependencies in a table fitting the width
+pub fn print_detailed_pkg_info(pkg: raur::Package, options: &InfoOptions) {
+    let part_of = if pkg.package_base != pkg.name {
+        format!(" (part of {})", pkg.package_base)
+    } else {
+        String::new()
+    };
+    println!("Name: {}{}; Version: {}", pkg.name, part_of, pkg.version);
     println!(
-        "Name: {}; Version: {}; Updated: {}",
-        pkg.name, pkg.version, last_mod
+        "Submitted: {}; Updated: {}",
+        format_timestamp(pkg.first_submitted, &options.date_format),
+        format_timestamp(pkg.last_modified, &options.date_format)
     );
+    if let Some(out_of_date) = pkg.out_of_date {
+        println!(
+            "Flagged out of date: {}",
+            format_timestamp(out_of_date, &options.date_format)
+        );
+    }
     println!(
         "Description: {}",
         pkg.description
             .unwrap_or("no description available".to_string())
     );
-    let mut dep = pkg.depends;
-    let mut mdep = pkg.make_depends;
-    let mut odep = pkg.opt_depends;
-    let mut cdep = pkg.check_depends;
-    let max_dep_size = dep.len().max(mdep.len()).max(odep.len()).max(cdep.len());
-    dep.resize(max_dep_size, "".to_string());
-    mdep.resize(max_dep_size, "".to_string());
-    odep.resize(max_dep_size, "".to_string());
-    cdep.resize(max_dep_size, "".to_string());
-
-    let big_dep = dep
-        .iter()
-        .zip(mdep.iter())
-        .zip(odep.iter())
-        .zip(cdep.iter()); // hehe ;)
-
-    println!(
-        "{: <25} | {: <25} | {: <25} | {: <25}",
-        "[Runtime]", "[Make]", "[Optional]", "[Check]"
+    let columns: Vec<Vec<String>> = [
+        pkg.depends,
+        pkg.make_depends,
+        pkg.opt_depends,
+        pkg.check_depends,
+    ]
+    .into_iter()
+    .map(|deps| {
+        deps.iter()
+            .map(|dep| format!("{} {}", options.deps.source(dep).mark(), dep))
+            .collect()
+    })
+    .collect();
+    let lines = table::render_columns(
+        &["[Runtime]", "[Make]", "[Optional]", "[Check]"],
+        &columns,
+        options.width,
+        options.overflow,
     );
-    for (((d, md), od), cd) in big_dep {
-        println!("{: <25} | {: <25} | {: <25} | {: <25}", d, md, od, cd);
+    for line in lines {
+        println!("{}", line);
     }
-    println!("");
+    println!("(i installed, r in a repository, a AUR only)");
+    println!();
     println!(
         "Upstream: {}",
         pkg.url.unwrap_or("not available".to_string())
@@ -298,8 +1129,20 @@ pub fn print_detailed_pkg_info(pkg: raur::Package) {
     println!("Git Url: https://aur.archlinux.org/{}", pkg.package_base);
 }
 
-pub fn print_simple_pkg_info(pkg: raur::Package) {
-    println!("Name: {}; Popularity: {}", pkg.name, pkg.popularity);
+// prints a search result, in_aur_dir marks packages already downloaded to the AUR dir
+pub fn print_simple_pkg_info(pkg: raur::Package, in_aur_dir: bool) {
+    let mut marks = String::new();
+    if let Some(out_of_date) = pkg.out_of_date {
+        let age = state::now_secs().saturating_sub(out_of_date.max(0) as u64);
+        marks.push_str(&format!(" [flagged out of date {}]", date::format_age(age)));
+    }
+    if in_aur_dir {
+        marks.push_str(" [in AUR dir]");
+    }
+    println!(
+        "Name: {}; Votes: {}; Popularity: {:.2}{}",
+        pkg.name, pkg.num_votes, pkg.popularity, marks
+    );
     println!(
         "Description: {}",
         pkg.description
@@ -314,48 +1157,77 @@ pub fn get_set_diff(bigger_dir: Vec<PathBuf>, containing_dir: Vec<PathBuf>) -> V
     let bigger_set: HashSet<PathBuf> = bigger_dir.into_iter().collect();
     let containing_set: HashSet<PathBuf> = containing_dir.into_iter().collect();
 
-    let diff: HashSet<PathBuf> = bigger_set
-        .difference(&containing_set)
-        .into_iter()
-        .map(|x| x.clone())
-        .collect();
-    return diff.into_iter().collect();
+    let diff: HashSet<PathBuf> = bigger_set.difference(&containing_set).cloned().collect();
+    diff.into_iter().collect()
 }
 
-pub async fn ext_search_aur(search_name: &String) {
-    let raur_handler = raur::Handle::new();
-    match raur_handler.search(search_name.clone()).await {
+// the package base of the search result is downloaded to the AUR dir
+pub fn in_aur_dir(aur_path: &Path, pkg: &raur::Package) -> bool {
+    aur_path.join(&pkg.package_base).is_dir()
+}
+
+pub async fn ext_search_aur(terms: &[String], options: &search::SearchOptions, aur_path: &Path) {
+    match search::search(terms, options).await {
         Ok(pkg_vec) => {
+            if pkg_vec.is_empty() {
+                println!("Couldn't find a package for '{}'", terms.join(" "));
+            }
             for pkg in pkg_vec {
-                print_simple_pkg_info(pkg);
+                let in_aur_dir = in_aur_dir(aur_path, &pkg);
+                print_simple_pkg_info(pkg, in_aur_dir);
             }
         }
         Err(err) => {
-            println!("Error while searching for {}: \n {}", search_name, err);
+            println!("Error while searching for {}: \n {}", terms.join(" "), err);
+        }
+    }
+}
+
+// the packages of the info request in the order of the names and the names not in the AUR. The
+// names can be dependencies with a version like foo>=1.0, duplicates are dropped
+pub fn order_info(names: &[String], pkgs: &[raur::Package]) -> (Vec<raur::Package>, Vec<String>) {
+    let mut found: Vec<raur::Package> = Vec::new();
+    let mut not_found: Vec<String> = Vec::new();
+    for name in names {
+        let name = srcinfo::dep_name(name);
+        match pkgs.iter().find(|pkg| pkg.name == name) {
+            Some(pkg) if !found.iter().any(|found| found.name == pkg.name) => {
+                found.push(pkg.clone())
+            }
+            Some(_) => {}
+            None if !not_found.iter().any(|missing| missing == name) => {
+                not_found.push(name.to_string())
+            }
+            None => {}
         }
     }
+    (found, not_found)
 }
 
-pub async fn search_aur(search_name: &String) {
+// prints the details of the packages, fetched with one info request
+pub async fn search_aur(names: &[String], options: &InfoOptions) {
     let raur_handler = raur::Handle::new();
-    match raur_handler.info(&[search_name.clone()]).await {
+    let pkgnames: Vec<&str> = names.iter().map(|name| srcinfo::dep_name(name)).collect();
+    match raur_handler.info(&pkgnames).await {
         Ok(pkg_vec) => {
-            //println!("Pkg_vec len {}", pkg_vec.len());
-            let pkg = match pkg_vec.first() {
-                Some(p) => p,
-                None => {
-                    println!(
-                        "Couldn't find a package named '{}', try -Ss.",
-                        search_name.clone()
-                    );
-                    return;
-                }
-            };
-            let pkg = pkg.clone();
-            print_detailed_pkg_info(pkg);
+            let (found, not_found) = order_info(names, &pkg_vec);
+            for pkg in found {
+                print_detailed_pkg_info(pkg, options);
+                println!("==================================");
+            }
+            if !not_found.is_empty() {
+                println!(
+                    "Couldn't find {} in the AUR, try -Ss.",
+                    not_found
+                        .iter()
+                        .map(|name| format!("'{}'", name))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                );
+            }
         }
         Err(err) => {
-            println!("Error while searching for {}: \n {}", search_name, err);
+            println!("Error while searching for {}: \n {}", names.join(" "), err);
         }
     }
 }
@@ -367,7 +1239,7 @@ mod tests {
         let tmp_path = "/tmp/aur_helper_rs_test/utf8_conversion_test/";
         prepair_tmp_dir(tmp_path);
         Command::new("git")
-            .current_dir(&tmp_path)
+            .current_dir(tmp_path)
             .arg("clone")
             .arg("https://aur.archlinux.org/sway-audio-idle-inhibit-git.git")
             .status()
@@ -432,11 +1304,372 @@ mod tests {
         }
     }
 
+    // Helper function to write a minimal .SRCINFO into a (new) package dir
+    fn write_srcinfo(pkg_dir: &str, pkgbase: &str, pkgver: &str, pkgnames: &[&str]) {
+        fs::create_dir_all(pkg_dir).expect("Couldn't create the package dir");
+        let mut content = format!(
+            "pkgbase = {}\n\tpkgver = {}\n\tpkgrel = 1\n\tarch = any\n\n",
+            pkgbase, pkgver
+        );
+        for name in pkgnames {
+            content.push_str(&format!("pkgname = {}\n\n", name));
+        }
+        fs::write(Path::new(pkg_dir).join(".SRCINFO"), content).expect("Couldn't write .SRCINFO");
+    }
+
+    #[test]
+    fn find_repo_packages_test() {
+        let tmp_path = "/tmp/aur_helper_rs_test/find_repo_packages_test/";
+        prepair_tmp_dir(tmp_path);
+        let aur_path = tmp_path.to_owned() + "aur/";
+        let db_path = Path::new(tmp_path).join("db");
+        write_srcinfo(
+            &(aur_path.clone() + "foo"),
+            "foo",
+            "1.0",
+            &["foo", "foo-docs"],
+        );
+        write_srcinfo(&(aur_path.clone() + "bar"), "bar", "1.0", &["bar"]);
+        write_srcinfo(
+            &(aur_path.clone() + "qux"),
+            "qux",
+            "1.0",
+            &["qux", "qux-docs"],
+        );
+        pacman_db::tests::prepair_pacman_db(
+            &db_path,
+            &[
+                ("extra", "foo-docs", "1.1-1", &[]),
+                ("extra", "baz", "1.0-1", &[]),
+                ("extra", "qux", "1.1-1", &[]),
+                ("extra", "qux-docs", "1.1-1", &[]),
+            ],
+            &[],
+        );
+
+        let dirs = get_dirs(Path::new(&aur_path), true).unwrap();
+        let mut found = find_repo_packages(dirs, &db_path).unwrap();
+        found.sort_by(|a, b| a.dir.cmp(&b.dir));
+        assert_eq!(found.len(), 2);
+        // only foo-docs of the split package moved
+        assert_eq!(found[0].dir, Path::new(&aur_path).join("foo"));
+        assert_eq!(found[0].repo_pkgs.len(), 1);
+        assert_eq!(found[0].repo_pkgs[0].name, "foo-docs");
+        assert_eq!(found[0].repo_pkgs[0].repo, "extra");
+        assert_eq!(found[0].aur_only, vec!["foo".to_string()]);
+        assert!(!found[0].complete());
+        assert_eq!(found[1].dir, Path::new(&aur_path).join("qux"));
+        assert_eq!(found[1].repo_pkgs.len(), 2);
+        assert!(found[1].complete());
+
+        clean_up_tmp_dir(tmp_path);
+    }
+
+    #[test]
+    fn get_package_status_test() {
+        let tmp_path = "/tmp/aur_helper_rs_test/get_package_status_test/";
+        prepair_tmp_dir(tmp_path);
+        let aur_path = Path::new(tmp_path).join("aur");
+        let pkg_dir = aur_path.join("foo");
+        let db_path = Path::new(tmp_path).join("db");
+        let conf_path = Path::new(tmp_path).join("pacman.conf");
+        write_srcinfo(pkg_dir.to_str().unwrap(), "foo", "1.1", &["foo"]);
+        Command::new("git")
+            .current_dir(&pkg_dir)
+            .arg("init")
+            .arg("-q")
+            .status()
+            .expect("Failed to init the test repo");
+        Command::new("git")
+            .current_dir(&pkg_dir)
+            .arg("add")
+            .arg(".SRCINFO")
+            .status()
+            .expect("Failed to stage in the test repo");
+        pacman_db::tests::prepair_pacman_db(&db_path, &[], &[("foo", "1.0-1", &[])]);
+        fs::write(&conf_path, "[options]\nIgnorePkg = bar foo\n").unwrap();
+        state::write_build_record(
+            &pkg_dir,
+            &BuildRecord {
+                time: 1,
+                success: true,
+                status: "exit status: 0".to_string(),
+                makepkg_args: vec!["-s".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let status = get_package_status(get_dirs(&aur_path, true).unwrap(), &db_path, &conf_path);
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].pkgbase, "foo");
+        assert_eq!(status[0].local_version.as_deref(), Some("1.1-1"));
+        assert_eq!(status[0].installed_version.as_deref(), Some("1.0-1"));
+        assert!(status[0].git.as_ref().unwrap().dirty);
+        assert!(status[0].latest_build.is_none());
+        assert!(status[0].ignored);
+        assert!(!status[0].held);
+        assert!(status[0].last_build.as_ref().unwrap().success);
+
+        clean_up_tmp_dir(tmp_path);
+    }
+
+    #[test]
+    fn get_makepkg_args_test() {
+        let tmp_path = "/tmp/aur_helper_rs_test/get_makepkg_args_test/";
+        prepair_tmp_dir(tmp_path);
+        write_srcinfo(&(tmp_path.to_owned() + "foo"), "foo", "1.0", &["foo"]);
+        let config = Config::parse(
+            "[options]\nMakepkgArgs = --skippgpcheck\n[foo]\nMakepkgArgs = --nocheck --holdver\n",
+        )
+        .unwrap();
+        let options = BuildOptions {
+            makepkg_args: vec!["-f".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            get_makepkg_args(&Path::new(tmp_path).join("foo"), &config, &options),
+            vec!["-s", "--skippgpcheck", "--nocheck", "--holdver", "-f"]
+        );
+        assert_eq!(
+            get_makepkg_args(&Path::new(tmp_path).join("bar"), &config, &options),
+            vec!["-s", "--skippgpcheck", "-f"]
+        );
+
+        let config = Config::parse("[options]\nSandbox = true\n[foo]\nSandbox = false\n").unwrap();
+        assert!(!use_sandbox(
+            &Path::new(tmp_path).join("foo"),
+            &config,
+            &options
+        ));
+        assert!(use_sandbox(
+            &Path::new(tmp_path).join("bar"),
+            &config,
+            &options
+        ));
+        let options = BuildOptions {
+            sandbox: true,
+            ..Default::default()
+        };
+        assert!(use_sandbox(
+            &Path::new(tmp_path).join("foo"),
+            &config,
+            &options
+        ));
+
+        clean_up_tmp_dir(tmp_path);
+    }
+
+    #[test]
+    fn is_build_current_test() {
+        let tmp_path = Path::new("/tmp/aur_helper_rs_test/is_build_current_test");
+        let pkg = git::tests::prepair_git_repos(tmp_path);
+        write_srcinfo(pkg.to_str().unwrap(), "pkg", "1.0", &["pkg", "pkg-docs"]);
+        let artifact = pkg.join("pkg-1.0-1-any.pkg.tar.zst");
+
+        fs::write(&artifact, "").unwrap();
+        assert!(!is_build_current(&pkg, ".pkg.tar.zst"));
+        fs::write(pkg.join("pkg-docs-1.0-1-any.pkg.tar.zst"), "").unwrap();
+        assert!(is_build_current(&pkg, ".pkg.tar.zst"));
+        assert!(!is_build_current(&pkg, ".pkg.tar.xz"));
+
+        // built before the last pull
+        Command::new("touch")
+            .arg("-d")
+            .arg("@0")
+            .arg(&artifact)
+            .status()
+            .expect("Couldn't touch the artifact");
+        assert!(!is_build_current(&pkg, ".pkg.tar.zst"));
+
+        clean_up_tmp_dir(tmp_path.to_str().unwrap());
+    }
+
+    #[test]
+    fn is_build_current_after_patches_test() {
+        let tmp_path = Path::new("/tmp/aur_helper_rs_test/is_build_current_after_patches_test");
+        let pkg = git::tests::prepair_git_repos(tmp_path);
+        write_srcinfo(pkg.to_str().unwrap(), "pkg", "1.0", &["pkg"]);
+        let patch_dir = tmp_path.join(patches::PATCHES_DIR).join("pkg");
+        fs::create_dir_all(&patch_dir).unwrap();
+        fs::write(
+            patch_dir.join("01-readme.patch"),
+            "--- a/README\n+++ b/README\n@@ -1 +1 @@\n-readme\n+patched\n",
+        )
+        .unwrap();
+
+        let patch_files = patches::find_patches(&pkg).unwrap();
+        let snapshot = patches::apply_patches(&pkg, &patch_files).unwrap();
+        fs::write(pkg.join("pkg-1.0-1-any.pkg.tar.zst"), "").unwrap();
+        // the reset of the revert is newer than the build
+        std::thread::sleep(Duration::from_millis(1100));
+        patches::revert_patches(&pkg, snapshot).unwrap();
+        assert!(is_build_current(&pkg, ".pkg.tar.zst"));
+
+        clean_up_tmp_dir(tmp_path.to_str().unwrap());
+    }
+
+    #[test]
+    fn dep_sources_test() {
+        let db_pkg = |name: &str, provides: &[&str]| DbPackage {
+            name: name.to_string(),
+            provides: provides.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        };
+        let sources = DepSources {
+            installed: vec![db_pkg("glibc", &[])],
+            repo: vec![db_pkg("glibc", &[]), db_pkg("rustup", &["rust=1.80"])],
+        };
+        assert_eq!(sources.source("glibc>=2.38"), DepSource::Installed);
+        assert_eq!(sources.source("rust"), DepSource::Repo);
+        assert_eq!(sources.source("foo-git: for foo"), DepSource::Aur);
+    }
+
+    #[test]
+    fn order_info_test() {
+        let pkg = |name: &str| raur::Package {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let names: Vec<String> = ["zlib-ng", "foo>=1.0", "missing", "foo", "missing=2"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let (found, not_found) = order_info(&names, &[pkg("foo"), pkg("zlib-ng")]);
+        let found: Vec<&str> = found.iter().map(|pkg| pkg.name.as_str()).collect();
+        assert_eq!(found, vec!["zlib-ng", "foo"]);
+        assert_eq!(not_found, vec!["missing".to_string()]);
+    }
+
+    #[test]
+    fn group_by_pkgbase_test() {
+        let pkg = |name: &str, pkgbase: &str| raur::Package {
+            name: name.to_string(),
+            package_base: pkgbase.to_string(),
+            ..Default::default()
+        };
+        let pkgs = vec![
+            pkg("foo-headers", "foo"),
+            pkg("foo-docs", "foo"),
+            pkg("bar", "bar"),
+        ];
+        let names: Vec<String> = ["foo-headers", "bar", "foo-docs", "missing", "foo-headers"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let (groups, not_found) = group_by_pkgbase(&names, &pkgs);
+        assert_eq!(
+            groups,
+            vec![
+                (
+                    "foo".to_string(),
+                    vec!["foo-headers".to_string(), "foo-docs".to_string()]
+                ),
+                ("bar".to_string(), vec!["bar".to_string()])
+            ]
+        );
+        assert_eq!(not_found, vec!["missing".to_string()]);
+    }
+
+    #[test]
+    fn install_packages_versions_test() {
+        let tmp_path = "/tmp/aur_helper_rs_test/install_packages_versions_test/";
+        prepair_tmp_dir(tmp_path);
+        let aur_path = Path::new(tmp_path).join("aur");
+        let db_path = Path::new(tmp_path).join("db");
+        for (dir, file) in [
+            ("foo", "foo-1.0-1-any.pkg.tar.zst"),
+            ("bar", "bar-2.0-1-any.pkg.tar.zst"),
+            ("baz", "baz-1:0.1-1-any.pkg.tar.zst"),
+            ("qux", "qux-1.0-1-any.pkg.tar.zst"),
+        ] {
+            fs::create_dir_all(aur_path.join(dir)).unwrap();
+            fs::write(aur_path.join(dir).join(file), "").unwrap();
+        }
+        fs::create_dir_all(aur_path.join("empty")).unwrap();
+        pacman_db::tests::prepair_pacman_db(
+            &db_path,
+            &[],
+            &[
+                ("foo", "1.0-1", &[]),
+                ("bar", "1.0-1", &[]),
+                ("qux", "0:1.0-1", &[]),
+            ],
+        );
+        let mut options = InstallOptions {
+            db_path,
+            reinstall: false,
+        };
+
+        let plan = install_packages(
+            get_dirs(&aur_path, true).unwrap(),
+            &Config::default(),
+            &options,
+        );
+        let mut install: Vec<(String, Option<String>)> = plan
+            .install
+            .iter()
+            .map(|entry| (entry.name.clone(), entry.installed.clone()))
+            .collect();
+        install.sort();
+        assert_eq!(
+            install,
+            vec![
+                ("bar".to_string(), Some("1.0-1".to_string())),
+                ("baz".to_string(), None)
+            ]
+        );
+        // the same version with the default epoch written out is unchanged too
+        let mut unchanged: Vec<&str> = plan
+            .unchanged
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        unchanged.sort();
+        assert_eq!(unchanged, vec!["foo", "qux"]);
+        assert!(plan.unchanged.iter().all(|entry| entry.is_unchanged()));
+        assert_eq!(plan.failed, vec![aur_path.join("empty")]);
+        assert_eq!(plan.command().unwrap().get_args().count(), 4);
+
+        options.reinstall = true;
+        let plan = install_packages(vec![aur_path.join("foo")], &Config::default(), &options);
+        assert_eq!(plan.install.len(), 1);
+        assert!(plan.unchanged.is_empty());
+        assert!(plan.install[0].is_unchanged());
+        assert!(!plan.install[0].is_downgrade());
+
+        // of a split package only the requested packages are installed
+        let split = aur_path.join("split");
+        fs::create_dir_all(&split).unwrap();
+        for file in [
+            "split-1.0-1-any.pkg.tar.zst",
+            "split-headers-1.0-1-any.pkg.tar.zst",
+            "split-docs-1.0-1-any.pkg.tar.zst",
+        ] {
+            fs::write(split.join(file), "").unwrap();
+        }
+        state::add_requested(
+            &split,
+            &["split-headers".to_string(), "split-docs".to_string()],
+        )
+        .unwrap();
+        let plan = install_packages(vec![split], &Config::default(), &options);
+        let names: Vec<&str> = plan
+            .install
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["split-headers", "split-docs"]);
+
+        clean_up_tmp_dir(tmp_path);
+    }
+
     #[test]
     fn get_dirs_detects_right_test() {
         // prepair
         let tmp_path = "/tmp/aur_helper_rs_test/get_dirs_detects_right_test".to_string();
-        prepair_tmp_dir(&tmp_path.as_str());
+        prepair_tmp_dir(tmp_path.as_str());
         Command::new("mkdir")
             .current_dir(tmp_path.as_str())
             .arg("test_dir1")
@@ -446,16 +1679,20 @@ mod tests {
             .expect("Couldn't create subdirs in test");
 
         // TEST
-        let paths = get_dirs(Path::new("/tmp/aur_helper_rs_test/"), true);
+        let paths = get_dirs(Path::new(&tmp_path), true);
 
         assert!(paths.is_ok());
 
-        let mut actual_paths: Vec<PathBuf> = Vec::new();
-        actual_paths.push(Path::new(&(tmp_path.clone() + "test_dir1")).to_path_buf());
-        actual_paths.push(Path::new(&(tmp_path.clone() + "test_dir2")).to_path_buf());
-        actual_paths.push(Path::new(&(tmp_path.clone() + "test_dir3")).to_path_buf());
+        let mut actual_paths: Vec<PathBuf> = vec![
+            Path::new(&(tmp_path.clone() + "/test_dir1")).to_path_buf(),
+            Path::new(&(tmp_path.clone() + "/test_dir2")).to_path_buf(),
+            Path::new(&(tmp_path.clone() + "/test_dir3")).to_path_buf(),
+        ];
+        let mut paths = paths.unwrap();
+        paths.sort();
+        actual_paths.sort();
 
-        assert_eq!(paths.unwrap().sort(), actual_paths.sort());
+        assert_eq!(paths, actual_paths);
 
         // clean up
         clean_up_tmp_dir(&tmp_path);
@@ -466,7 +1703,7 @@ mod tests {
         // prepair
         let tmp_path = "/tmp/aur_helper_rs_test/get_dirs_error_test";
         let test_file = tmp_path.to_owned() + "/test_file.txt";
-        prepair_tmp_dir(&tmp_path);
+        prepair_tmp_dir(tmp_path);
         println!("{}", test_file.as_str());
 
         Command::new("touch")
@@ -482,15 +1719,16 @@ mod tests {
         assert!(err2.is_err());
 
         // clean up
-        clean_up_tmp_dir(&tmp_path);
+        clean_up_tmp_dir(tmp_path);
     }
 
     #[test]
     fn update_packages_test() {
         let tmp_path = "/tmp/aur_helper_rs_test/update_packages_test/";
-        let mut git_links: Vec<String> = Vec::new();
-        git_links.push("https://aur.archlinux.org/swaylock-blur-bin.git".to_owned());
-        git_links.push("https://aur.archlinux.org/yofi-bin.git".to_owned());
+        let git_links: Vec<String> = vec![
+            "https://aur.archlinux.org/swaylock-blur-bin.git".to_owned(),
+            "https://aur.archlinux.org/yofi-bin.git".to_owned(),
+        ];
         prepair_aur_test_dir(tmp_path, &git_links);
         let update_dir_path = tmp_path.to_owned() + "yofi-bin";
 
@@ -508,11 +1746,12 @@ mod tests {
             .expect("Failed to restore the repo yofi-bin");
         let dirs = get_dirs(Path::new(tmp_path), true);
         assert!(dirs.is_ok());
-        let mut updated_dirs: Vec<PathBuf> = Vec::new();
-        updated_dirs.push(Path::new(&update_dir_path).to_path_buf());
-        let success_dirs = update_packages(dirs.unwrap());
-        assert!(success_dirs.is_ok());
-        for i in iter::zip(success_dirs.unwrap(), updated_dirs) {
+        let updated_dirs: Vec<PathBuf> = vec![Path::new(&update_dir_path).to_path_buf()];
+        let results = update_packages(dirs.unwrap(), &Config::default(), &mut |_, _| {
+            UpdateStrategy::Skip
+        });
+        assert!(results.failed.is_empty());
+        for i in iter::zip(results.updated, updated_dirs) {
             assert_eq!(i.0, i.1);
         }
 
@@ -527,10 +1766,10 @@ mod tests {
         git_links.push("https://aur.archlinux.org/yofi-bin".to_owned());
         prepair_aur_test_dir(tmp_path, &git_links);
 
-        let mut dirs = get_dirs(&Path::new(tmp_path), true);
+        let mut dirs = get_dirs(Path::new(tmp_path), true);
         assert!(dirs.is_ok());
-        let no_err = build_packages(dirs.unwrap());
-        assert!(no_err.is_ok());
+        let no_err = build_packages(dirs.unwrap(), &Config::default(), &BuildOptions::default());
+        assert!(no_err.failed.is_empty());
 
         clean_up_tmp_dir(tmp_path);
 
@@ -539,10 +1778,10 @@ mod tests {
 
         prepair_aur_test_dir(tmp_path, &git_links);
 
-        dirs = get_dirs(&Path::new(tmp_path), true);
+        dirs = get_dirs(Path::new(tmp_path), true);
         assert!(dirs.is_ok());
-        let err = build_packages(dirs.unwrap());
-        assert!(err.is_err());
+        let err = build_packages(dirs.unwrap(), &Config::default(), &BuildOptions::default());
+        assert!(!err.failed.is_empty());
 
         clean_up_tmp_dir(tmp_path);
     }
@@ -550,22 +1789,23 @@ mod tests {
     #[test]
     fn install_packages_test() {
         let tmp_path = "/tmp/aur_helper_rs_test/find_build_packages/";
-        let mut git_links: Vec<String> = Vec::new();
-        git_links.push("https://aur.archlinux.org/swaylock-blur-bin.git".to_owned());
-        git_links.push("https://aur.archlinux.org/packages/yofi-bin".to_owned());
-        git_links.push("https://aur.archlinux.org/piow-bin.git".to_owned());
+        let git_links: Vec<String> = vec![
+            "https://aur.archlinux.org/swaylock-blur-bin.git".to_owned(),
+            "https://aur.archlinux.org/packages/yofi-bin".to_owned(),
+            "https://aur.archlinux.org/piow-bin.git".to_owned(),
+        ];
         prepair_aur_test_dir(tmp_path, &git_links);
 
-        let dirs = get_dirs(&Path::new(tmp_path), true);
+        let dirs = get_dirs(Path::new(tmp_path), true);
         assert!(dirs.is_ok());
 
         let dirs = dirs.unwrap();
 
-        let no_err = build_packages(dirs.clone());
-        assert!(no_err.is_ok());
+        let no_err = build_packages(dirs.clone(), &Config::default(), &BuildOptions::default());
+        assert!(no_err.failed.is_empty());
 
-        let no_err = install_packages(dirs.clone());
-        assert!(no_err.is_ok());
+        let no_err = install_packages(dirs.clone(), &Config::default(), &InstallOptions::default());
+        assert!(no_err.failed.is_empty());
 
         clean_up_tmp_dir(tmp_path);
     }
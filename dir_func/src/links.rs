@@ -0,0 +1,278 @@
+use crate::config::Config;
+
+// the git base of the AUR, overwritten by AurUrl in the config
+pub const DEFAULT_AUR_URL: &str = "https://aur.archlinux.org/";
+
+// the git base of the AUR for maintainers with push access, overwritten by AurSshUrl
+pub const DEFAULT_AUR_SSH_URL: &str = "ssh://aur@aur.archlinux.org/";
+
+const GITHUB_URL: &str = "https://github.com/";
+
+// the url schemes git clone is allowed to use
+const SCHEMES: [&str; 5] = ["https://", "http://", "ssh://", "git://", "file://"];
+
+fn aur_base(config: &Config, ssh: bool) -> String {
+    let base = if ssh {
+        config.get("AurSshUrl").unwrap_or(DEFAULT_AUR_SSH_URL)
+    } else {
+        config.get("AurUrl").unwrap_or(DEFAULT_AUR_URL)
+    };
+    if base.ends_with('/') {
+        base.to_string()
+    } else {
+        format!("{}/", base)
+    }
+}
+
+// a valid AUR package name: lowercase alphanumerics and @._+-, not starting with - or .
+fn is_valid_pkgname(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(['-', '.'])
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "@._+-".contains(c))
+}
+
+// checks a git url before cloning: a known scheme with a host and a path, an scp-like
+// user@host:path or an absolute path of a local repo. Nothing git could read as an option
+pub fn validate_url(url: &str) -> Result<(), String> {
+    if url.starts_with('-') || url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(format!("'{}' isn't a valid git url", url));
+    }
+    if url.starts_with('/') {
+        return Ok(());
+    }
+    if let Some(scheme) = SCHEMES.iter().find(|scheme| url.starts_with(**scheme)) {
+        let rest = &url[scheme.len()..];
+        let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+        if (host.is_empty() && *scheme != "file://") || path.is_empty() {
+            return Err(format!("'{}' has no host or path", url));
+        }
+        return Ok(());
+    }
+    if url.contains("://") {
+        return Err(format!("'{}' has an unsupported scheme", url));
+    }
+    // scp-like syntax, e.g. aur@aur.archlinux.org:foo.git
+    match url.split_once(':') {
+        Some((host, path)) if host.contains('@') && !path.is_empty() => Ok(()),
+        _ => Err(format!("'{}' is neither a package name nor a git url", url)),
+    }
+}
+
+// the AUR package name of a download argument: a bare name, aur:<pkg> or the AUR package page
+// <AurUrl>packages/<pkg>. None for other urls
+pub fn aur_name<'a>(spec: &'a str, config: &Config) -> Option<&'a str> {
+    if let Some(name) = spec.strip_prefix("aur:") {
+        return Some(name);
+    }
+    if !spec.contains(['/', ':']) {
+        return Some(spec);
+    }
+    spec.trim_end_matches('/')
+        .strip_prefix(&format!("{}packages/", aur_base(config, false)))
+}
+
+// the git url of an AUR package base, the ssh url with ssh = true
+pub fn aur_link(pkgbase: &str, config: &Config, ssh: bool) -> Result<String, String> {
+    if is_valid_pkgname(pkgbase) {
+        Ok(format!("{}{}.git", aur_base(config, ssh), pkgbase))
+    } else {
+        Err(format!("'{}' isn't a valid AUR package name", pkgbase))
+    }
+}
+
+// resolves a download argument to a git url: an AUR package name (see aur_name) to the AUR (the
+// ssh url with ssh = true), github:<user>/<repo> to GitHub, other urls are validated and used as
+// they are
+pub fn resolve_link(spec: &str, config: &Config, ssh: bool) -> Result<String, String> {
+    if let Some(name) = aur_name(spec, config) {
+        return aur_link(name, config, ssh);
+    }
+    if let Some(repo) = spec.strip_prefix("github:") {
+        let repo = repo.trim_end_matches(".git");
+        return match repo.split_once('/') {
+            Some((user, name)) if !user.is_empty() && !name.is_empty() && !name.contains('/') => {
+                let url = format!("{}{}.git", GITHUB_URL, repo);
+                validate_url(&url).map(|_| url)
+            }
+            _ => Err(format!("'{}' isn't of the form github:<user>/<repo>", spec)),
+        };
+    }
+    validate_url(spec).map(|_| spec.to_string())
+}
+
+// the directory name for a clone of the url: the last path segment without a trailing slash and
+// the .git suffix, dots in package names like python-foo.bar are kept
+pub fn dir_name(url: &str) -> Option<String> {
+    let path = url.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let name = path.rsplit(['/', ':']).next()?;
+    if name.is_empty() || name == "." || name == ".." {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+// the url points into the AUR of the config, with the https or the ssh url. The ssh url is also
+// accepted in the scp-like form, e.g. aur@aur.archlinux.org:foo.git
+pub fn is_aur_url(url: &str, config: &Config) -> bool {
+    let ssh_base = aur_base(config, true);
+    let mut bases = vec![aur_base(config, false), ssh_base.clone()];
+    if let Some(host) = ssh_base
+        .strip_prefix("ssh://")
+        .and_then(|rest| rest.strip_suffix('/'))
+        .filter(|host| !host.contains('/'))
+    {
+        bases.push(format!("{}:", host));
+    }
+    bases.iter().any(|base| {
+        url.strip_prefix(base.as_str())
+            .map(normalize_url)
+            .is_some_and(is_valid_pkgname)
+    })
+}
+
+// the url without a trailing slash and .git suffix, to compare the remotes of checkouts
+pub fn normalize_url(url: &str) -> &str {
+    let url = url.trim_end_matches('/');
+    url.strip_suffix(".git").unwrap_or(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::FetchOptions;
+    use crate::{download_packages_from_git, DownloadOutcome};
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+
+    #[test]
+    fn resolve_link_test() {
+        let config = Config::default();
+        assert_eq!(
+            resolve_link("yofi-bin", &config, false).unwrap(),
+            "https://aur.archlinux.org/yofi-bin.git"
+        );
+        assert_eq!(
+            resolve_link("aur:yofi-bin", &config, true).unwrap(),
+            "ssh://aur@aur.archlinux.org/yofi-bin.git"
+        );
+        assert_eq!(
+            resolve_link("github:Nikl174/aur_helper", &config, false).unwrap(),
+            "https://github.com/Nikl174/aur_helper.git"
+        );
+        assert_eq!(
+            resolve_link("aur@aur.archlinux.org:foo.git", &config, false).unwrap(),
+            "aur@aur.archlinux.org:foo.git"
+        );
+        assert!(resolve_link("Foo Bar", &config, false).is_err());
+        assert!(resolve_link("aur:-foo", &config, false).is_err());
+        assert!(resolve_link("github:user", &config, false).is_err());
+        assert!(resolve_link("ftp://example.org/foo.git", &config, false).is_err());
+        assert!(resolve_link("https://", &config, false).is_err());
+        assert!(resolve_link("--upload-pack=touch/x", &config, false).is_err());
+        assert_eq!(aur_name("aur:foo-headers", &config), Some("foo-headers"));
+        assert_eq!(aur_name("github:user/repo", &config), None);
+        assert_eq!(aur_name("https://example.org/foo.git", &config), None);
+
+        assert_eq!(
+            resolve_link(
+                "https://aur.archlinux.org/packages/yofi-bin/",
+                &config,
+                false
+            )
+            .unwrap(),
+            "https://aur.archlinux.org/yofi-bin.git"
+        );
+
+        let config = Config::parse("[options]\nAurUrl = https://aur.example.org").unwrap();
+        assert_eq!(
+            resolve_link("foo", &config, false).unwrap(),
+            "https://aur.example.org/foo.git"
+        );
+    }
+
+    #[test]
+    fn is_aur_url_test() {
+        let config = Config::default();
+        assert!(is_aur_url("https://aur.archlinux.org/foo.git", &config));
+        assert!(is_aur_url("https://aur.archlinux.org/foo", &config));
+        assert!(is_aur_url("ssh://aur@aur.archlinux.org/foo.git", &config));
+        assert!(is_aur_url("aur@aur.archlinux.org:foo.git", &config));
+        assert!(!is_aur_url("https://github.com/foo/bar.git", &config));
+        assert!(!is_aur_url(
+            "https://aur.archlinux.org.evil/foo.git",
+            &config
+        ));
+        assert!(!is_aur_url(
+            "https://aur.archlinux.org/foo/bar.git",
+            &config
+        ));
+        let config = Config::parse("[options]\nAurUrl = file:///srv/aur").unwrap();
+        assert!(is_aur_url("file:///srv/aur/foo", &config));
+    }
+
+    #[test]
+    fn dir_name_test() {
+        assert_eq!(
+            dir_name("https://aur.archlinux.org/python-foo.bar.git").as_deref(),
+            Some("python-foo.bar")
+        );
+        assert_eq!(
+            dir_name("https://github.com/user/repo/").as_deref(),
+            Some("repo")
+        );
+        assert_eq!(
+            dir_name("aur@aur.archlinux.org:foo.git").as_deref(),
+            Some("foo")
+        );
+        assert_eq!(dir_name("/srv/git/bar.git/").as_deref(), Some("bar"));
+        assert_eq!(dir_name("https://example.org/.."), None);
+        assert_eq!(
+            normalize_url("https://aur.archlinux.org/foo.git/"),
+            normalize_url("https://aur.archlinux.org/foo")
+        );
+    }
+
+    #[test]
+    fn download_from_local_aur_test() {
+        let tmp_path = Path::new("/tmp/aur_helper_rs_test/download_from_local_aur_test");
+        let _ = fs::remove_dir_all(tmp_path);
+        let server = tmp_path.join("server");
+        let aur_path = tmp_path.join("aur");
+        fs::create_dir_all(&server).unwrap();
+        fs::create_dir_all(&aur_path).unwrap();
+        let status = Command::new("git")
+            .args(["init", "--quiet", "--bare", "foo.git"])
+            .current_dir(&server)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let config =
+            Config::parse(&format!("[options]\nAurUrl = file://{}", server.display())).unwrap();
+        let link = resolve_link("aur:foo", &config, false).unwrap();
+        assert_eq!(link, format!("file://{}/foo.git", server.display()));
+        let fetch = FetchOptions::default();
+        let outcomes = download_packages_from_git(&aur_path, vec![link.clone()], &fetch);
+        assert_eq!(
+            outcomes,
+            vec![(link.clone(), DownloadOutcome::Cloned(aur_path.join("foo")))]
+        );
+        assert!(aur_path.join("foo").join(".git").is_dir());
+
+        // the second download finds the checkout, a dir of another repo isn't touched
+        fs::create_dir_all(aur_path.join("bar")).unwrap();
+        let other = format!("file://{}/bar.git", server.display());
+        let outcomes = download_packages_from_git(&aur_path, vec![link, other], &fetch);
+        assert_eq!(
+            outcomes[0].1,
+            DownloadOutcome::Existing(aur_path.join("foo"))
+        );
+        assert!(matches!(outcomes[1].1, DownloadOutcome::Failed(_)));
+
+        let _ = fs::remove_dir_all(tmp_path);
+    }
+}
@@ -0,0 +1,264 @@
+use std::path::Path;
+use std::process::Command;
+use std::str::from_utf8;
+use std::{fs, io};
+
+// default database path of pacman, contains the 'sync' and 'local' directory
+pub const PACMAN_DB_PATH: &str = "/var/lib/pacman/";
+pub const PACMAN_CONF_PATH: &str = "/etc/pacman.conf";
+
+// a package entry of a pacman database, repo is 'local' for installed packages
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DbPackage {
+    pub name: String,
+    pub base: String,
+    pub version: String,
+    pub repo: String,
+    pub depends: Vec<String>,
+    pub optdepends: Vec<String>,
+    pub provides: Vec<String>,
+}
+
+// parses one or more concatenated 'desc' files of a pacman database
+pub fn parse_desc(content: &str, repo: &str) -> Vec<DbPackage> {
+    let mut packages: Vec<DbPackage> = Vec::new();
+    let mut current = DbPackage::default();
+    let mut key = "";
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            key = "";
+            continue;
+        }
+        if line.starts_with('%') && line.ends_with('%') && line.len() > 1 {
+            key = line;
+            if key == "%NAME%" && !current.name.is_empty() {
+                packages.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        match key {
+            "%NAME%" => current.name = line.to_string(),
+            "%BASE%" => current.base = line.to_string(),
+            "%VERSION%" => current.version = line.to_string(),
+            "%DEPENDS%" => current.depends.push(line.to_string()),
+            "%OPTDEPENDS%" => current.optdepends.push(line.to_string()),
+            "%PROVIDES%" => current.provides.push(line.to_string()),
+            _ => {}
+        }
+    }
+    if !current.name.is_empty() {
+        packages.push(current);
+    }
+    for pkg in packages.iter_mut() {
+        pkg.repo = repo.to_string();
+        if pkg.base.is_empty() {
+            pkg.base = pkg.name.clone();
+        }
+    }
+    packages
+}
+
+// reads all sync databases (db_path/sync/*.db), extracts the desc files with tar
+pub fn read_sync_dbs(db_path: &Path) -> Result<Vec<DbPackage>, io::Error> {
+    let mut db_files: Vec<_> = fs::read_dir(db_path.join("sync"))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "db"))
+        .collect();
+    db_files.sort();
+
+    let mut packages: Vec<DbPackage> = Vec::new();
+    for db_file in db_files {
+        let repo = db_file
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let output = Command::new("tar")
+            .arg("-xOf")
+            .arg(&db_file)
+            .arg("--wildcards")
+            .arg("*/desc")
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "Couldn't read the sync database {}: {}",
+                db_file.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        let content = from_utf8(&output.stdout)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        packages.append(&mut parse_desc(content, &repo));
+    }
+    Ok(packages)
+}
+
+// reads the installed packages from the local database (db_path/local/*/desc)
+pub fn read_local_db(db_path: &Path) -> Result<Vec<DbPackage>, io::Error> {
+    let mut packages: Vec<DbPackage> = Vec::new();
+    for entry in fs::read_dir(db_path.join("local"))? {
+        let desc = entry?.path().join("desc");
+        if !desc.is_file() {
+            continue;
+        }
+        packages.append(&mut parse_desc(&fs::read_to_string(desc)?, "local"));
+    }
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(packages)
+}
+
+// the pkgbase of an installed package or pkgbase and the names of its installed split packages
+pub fn installed_split_packages(
+    pkg: &str,
+    installed: &[DbPackage],
+) -> Option<(String, Vec<String>)> {
+    let base = installed
+        .iter()
+        .find(|package| package.base == pkg)
+        .or_else(|| installed.iter().find(|package| package.name == pkg))
+        .map(|package| package.base.clone())?;
+    let names = installed
+        .iter()
+        .filter(|package| package.base == base)
+        .map(|package| package.name.clone())
+        .collect();
+    Some((base, names))
+}
+
+// reads a list option like 'IgnorePkg' of the [options] section in the pacman.conf
+pub fn read_conf_option(conf_path: &Path, key: &str) -> Result<Vec<String>, io::Error> {
+    let mut values: Vec<String> = Vec::new();
+    let mut in_options = false;
+    for line in fs::read_to_string(conf_path)?.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.starts_with('[') {
+            in_options = line == "[options]";
+            continue;
+        }
+        if !in_options {
+            continue;
+        }
+        if let Some((conf_key, value)) = line.split_once('=') {
+            if conf_key.trim() == key {
+                values.extend(value.split_whitespace().map(|v| v.to_string()));
+            }
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // writes a fake pacman database with the given (repo, name, version, depends) entries
+    pub(crate) fn prepair_pacman_db(
+        db_path: &Path,
+        sync: &[(&str, &str, &str, &[&str])],
+        local: &[(&str, &str, &[&str])],
+    ) {
+        let _ = fs::remove_dir_all(db_path);
+        let sync_path = db_path.join("sync");
+        fs::create_dir_all(&sync_path).unwrap();
+        let mut repos: Vec<&str> = sync.iter().map(|s| s.0).collect();
+        repos.dedup();
+        for repo in repos {
+            let repo_dir = db_path.join(format!("{}-content", repo));
+            for (_, name, version, depends) in sync.iter().filter(|s| s.0 == repo) {
+                write_desc(
+                    &repo_dir.join(format!("{}-{}", name, version)),
+                    name,
+                    version,
+                    depends,
+                );
+            }
+            Command::new("tar")
+                .arg("-czf")
+                .arg(sync_path.join(format!("{}.db", repo)))
+                .arg("-C")
+                .arg(&repo_dir)
+                .arg(".")
+                .status()
+                .expect("Couldn't create the sync db with tar");
+        }
+        for (name, version, depends) in local {
+            let dir = db_path.join("local").join(format!("{}-{}", name, version));
+            write_desc(&dir, name, version, depends);
+        }
+    }
+
+    fn write_desc(dir: &Path, name: &str, version: &str, depends: &[&str]) {
+        fs::create_dir_all(dir).unwrap();
+        let mut desc = format!(
+            "%FILENAME%\n{}-{}-x86_64.pkg.tar.zst\n\n%NAME%\n{}\n\n%VERSION%\n{}\n\n",
+            name, version, name, version
+        );
+        if !depends.is_empty() {
+            desc.push_str(&format!("%DEPENDS%\n{}\n\n", depends.join("\n")));
+        }
+        fs::write(dir.join("desc"), desc).unwrap();
+    }
+
+    #[test]
+    fn read_pacman_db_test() {
+        let db_path = Path::new("/tmp/aur_helper_rs_test/read_pacman_db_test");
+        prepair_pacman_db(
+            db_path,
+            &[
+                ("core", "glibc", "2.40-1", &[]),
+                ("extra", "foo", "1.0-1", &["glibc"]),
+                ("extra", "bar", "2.0-3", &[]),
+            ],
+            &[("foo", "0.9-1", &["glibc"])],
+        );
+
+        let mut sync = read_sync_dbs(db_path).unwrap();
+        sync.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(sync.len(), 3);
+        assert_eq!(sync[1].name, "foo");
+        assert_eq!(sync[1].repo, "extra");
+        assert_eq!(sync[1].depends, vec!["glibc"]);
+        assert_eq!(sync[2].repo, "core");
+
+        let local = read_local_db(db_path).unwrap();
+        assert_eq!(local.len(), 1);
+        assert_eq!(local[0].version, "0.9-1");
+        assert_eq!(local[0].base, "foo");
+
+        let _ = fs::remove_dir_all(db_path);
+    }
+
+    #[test]
+    fn installed_split_packages_test() {
+        let package = |name: &str, base: &str| DbPackage {
+            name: name.to_string(),
+            base: base.to_string(),
+            ..Default::default()
+        };
+        let installed = vec![
+            package("foo", "foo-git"),
+            package("foo-docs", "foo-git"),
+            package("foo-git", "bar"),
+            package("glibc", "glibc"),
+        ];
+        // the pkgbase wins over a package of the same name
+        assert_eq!(
+            installed_split_packages("foo-git", &installed),
+            Some((
+                "foo-git".to_string(),
+                vec!["foo".to_string(), "foo-docs".to_string()]
+            ))
+        );
+        assert_eq!(
+            installed_split_packages("foo-docs", &installed),
+            installed_split_packages("foo-git", &installed)
+        );
+        assert_eq!(
+            installed_split_packages("glibc", &installed),
+            Some(("glibc".to_string(), vec!["glibc".to_string()]))
+        );
+        assert_eq!(installed_split_packages("baz", &installed), None);
+    }
+}
@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use crate::get_pkgbase;
+use crate::git::git_output;
+use crate::state::aur_path_of;
+
+// directory in the AUR dir with the user patches: .patches/<pkgbase>/*.patch
+pub const PATCHES_DIR: &str = ".patches";
+
+// keeps the snapshot of the local changes reachable, until they are reapplied after the build
+pub const SNAPSHOT_REF: &str = "refs/aur_helper/snapshot";
+
+// returns the sorted patches of the package, empty if there is no patch dir for it
+pub fn find_patches(pkg_dir: &Path) -> Result<Vec<PathBuf>, io::Error> {
+    let patch_dir = aur_path_of(pkg_dir)
+        .join(PATCHES_DIR)
+        .join(get_pkgbase(pkg_dir));
+    if !patch_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut patches: Vec<PathBuf> = fs::read_dir(patch_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "patch"))
+        .collect();
+    patches.sort();
+    Ok(patches)
+}
+
+// applies the patches to the working tree, falls back to a 3-way merge for each patch.
+// Returns a snapshot of the local changes before patching, needed by revert_patches.
+// On fail every patch is reverted and the failed patch with the git error is returned
+pub fn apply_patches(pkg_dir: &Path, patches: &[PathBuf]) -> Result<Option<String>, String> {
+    let snapshot = create_snapshot(pkg_dir).map_err(|err| err.to_string())?;
+    for patch in patches {
+        let patch_str = patch.to_string_lossy();
+        if git_output(pkg_dir, &["apply", "--index", &patch_str]).is_ok() {
+            continue;
+        }
+        if let Err(err) = git_output(pkg_dir, &["apply", "--3way", &patch_str]) {
+            if let Err(revert_err) = revert_patches(pkg_dir, snapshot) {
+                println!(
+                    "WARNING: couldn't revert the patches of {}: {}",
+                    pkg_dir.display(),
+                    revert_err
+                );
+            }
+            return Err(format!(
+                "{} doesn't apply: {}",
+                patch
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                err
+            ));
+        }
+    }
+    Ok(snapshot)
+}
+
+// records the uncommitted changes as a stash commit kept by SNAPSHOT_REF, None for a clean
+// working tree
+fn create_snapshot(pkg_dir: &Path) -> Result<Option<String>, io::Error> {
+    let snapshot = git_output(pkg_dir, &["stash", "create"])?
+        .trim()
+        .to_string();
+    if snapshot.is_empty() {
+        return Ok(None);
+    }
+    git_output(pkg_dir, &["update-ref", SNAPSHOT_REF, &snapshot])?;
+    Ok(Some(snapshot))
+}
+
+// resets the tracked files and reapplies the local changes from before the patches. If that fails,
+// the error names the snapshot, which is still kept by SNAPSHOT_REF
+pub fn revert_patches(pkg_dir: &Path, snapshot: Option<String>) -> Result<(), io::Error> {
+    let reset = git_output(pkg_dir, &["reset", "--hard", "--quiet", "HEAD"]);
+    let Some(snapshot) = snapshot else {
+        return reset.map(|_| ());
+    };
+    reset
+        .and_then(|_| {
+            git_output(
+                pkg_dir,
+                &["stash", "apply", "--index", "--quiet", &snapshot],
+            )
+        })
+        .map_err(|err| {
+            io::Error::other(format!(
+                "{}, the local changes are kept in {} ({}), restore them with 'git stash apply {}'",
+                err, snapshot, SNAPSHOT_REF, snapshot
+            ))
+        })?;
+    git_output(pkg_dir, &["update-ref", "-d", SNAPSHOT_REF])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::tests::prepair_git_repos;
+
+    const PATCH: &str = "--- a/PKGBUILD
++++ b/PKGBUILD
+@@ -1 +1 @@
+-pkgver=1
++pkgver=1.patched
+";
+
+    #[test]
+    fn apply_and_revert_patches_test() {
+        let tmp_path = Path::new("/tmp/aur_helper_rs_test/apply_and_revert_patches_test");
+        let pkg = prepair_git_repos(tmp_path);
+        let patch_dir = tmp_path.join(PATCHES_DIR).join("pkg");
+        fs::create_dir_all(&patch_dir).unwrap();
+        fs::write(patch_dir.join("01-version.patch"), PATCH).unwrap();
+        fs::write(patch_dir.join("notes.txt"), "not a patch").unwrap();
+        fs::write(pkg.join("README"), "local change\n").unwrap();
+
+        let patches = find_patches(&pkg).unwrap();
+        assert_eq!(patches, vec![patch_dir.join("01-version.patch")]);
+
+        let snapshot = apply_patches(&pkg, &patches).unwrap();
+        assert_eq!(
+            fs::read_to_string(pkg.join("PKGBUILD")).unwrap(),
+            "pkgver=1.patched\n"
+        );
+        assert_eq!(
+            git_output(&pkg, &["rev-parse", SNAPSHOT_REF])
+                .unwrap()
+                .trim(),
+            snapshot.as_deref().unwrap()
+        );
+        revert_patches(&pkg, snapshot).unwrap();
+        assert!(git_output(&pkg, &["rev-parse", "--verify", "-q", SNAPSHOT_REF]).is_err());
+        assert_eq!(
+            fs::read_to_string(pkg.join("PKGBUILD")).unwrap(),
+            "pkgver=1\n"
+        );
+        assert_eq!(
+            fs::read_to_string(pkg.join("README")).unwrap(),
+            "local change\n"
+        );
+
+        // a snapshot, which can't be reapplied, is named in the error
+        let missing = "1".repeat(40);
+        let err = revert_patches(&pkg, Some(missing.clone())).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&format!("git stash apply {}", missing)));
+
+        // a patch, which doesn't apply anymore, leaves the tree unchanged
+        fs::write(pkg.join("PKGBUILD"), "pkgver=2\n").unwrap();
+        let err = apply_patches(&pkg, &patches);
+        assert!(err
+            .unwrap_err()
+            .starts_with("01-version.patch doesn't apply"));
+        assert_eq!(
+            fs::read_to_string(pkg.join("PKGBUILD")).unwrap(),
+            "pkgver=2\n"
+        );
+
+        let _ = fs::remove_dir_all(tmp_path);
+    }
+}
@@ -0,0 +1,217 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::git::git_output;
+use crate::{process, srcinfo, state};
+
+// the default timeout of a key import, overwritten by KeyTimeout in the config
+pub const DEFAULT_KEY_TIMEOUT: Duration = Duration::from_secs(60);
+
+// the lookup in the local keyring only hangs on a stuck gpg-agent or lock
+const LIST_KEYS_TIMEOUT: Duration = Duration::from_secs(10);
+
+// the PGP keys of a package: the validpgpkeys missing in the keyring of the user and the changes
+// since the last build
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyCheck {
+    pub missing: Vec<String>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    // the keys, whose file in keys/pgp got a new content under the same fingerprint
+    pub replaced: Vec<String>,
+}
+
+impl KeyCheck {
+    pub fn changed(&self) -> bool {
+        !self.added.is_empty() || !self.removed.is_empty() || !self.replaced.is_empty()
+    }
+}
+
+// where a missing key is imported from
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeySource {
+    // keys/pgp/<fingerprint>.asc shipped in the AUR repo
+    Repo(PathBuf),
+    // <fingerprint>.asc in the KeyringDir of the config
+    Keyring(PathBuf),
+    Keyserver,
+}
+
+impl fmt::Display for KeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeySource::Repo(path) | KeySource::Keyring(path) => write!(f, "{}", path.display()),
+            KeySource::Keyserver => write!(f, "keyserver"),
+        }
+    }
+}
+
+// the key is in the keyring of the user
+pub fn has_key(key: &str) -> bool {
+    process::run_output(
+        Command::new("gpg").args(["--batch", "--list-keys", key]),
+        Some(LIST_KEYS_TIMEOUT),
+    )
+    .is_ok_and(|output| output.status.success())
+}
+
+// the keys only in new and the keys only in old
+fn key_changes(old: &[String], new: &[String]) -> (Vec<String>, Vec<String>) {
+    let added = new
+        .iter()
+        .filter(|key| !old.contains(key))
+        .cloned()
+        .collect();
+    let removed = old
+        .iter()
+        .filter(|key| !new.contains(key))
+        .cloned()
+        .collect();
+    (added, removed)
+}
+
+// the keys with a file keys/pgp/<fingerprint>.asc in the repo and the git hash of its content
+pub fn key_file_hashes(pkg_dir: &Path, keys: &[String]) -> Vec<(String, String)> {
+    keys.iter()
+        .filter_map(|key| {
+            let path = pkg_dir
+                .join("keys")
+                .join("pgp")
+                .join(format!("{}.asc", key));
+            if !path.is_file() {
+                return None;
+            }
+            let hash = git_output(pkg_dir, &["hash-object", "--", &path.to_string_lossy()]).ok()?;
+            Some((key.clone(), hash.trim().to_string()))
+        })
+        .collect()
+}
+
+// the keys, whose file has an other hash than at the last build
+fn replaced_keys(old: &[(String, String)], new: &[(String, String)]) -> Vec<String> {
+    new.iter()
+        .filter(|(key, hash)| {
+            old.iter()
+                .any(|(old_key, old_hash)| old_key == key && old_hash != hash)
+        })
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+// checks the validpgpkeys of the .SRCINFO against the keyring and the keys of the last build
+pub fn check_keys(pkg_dir: &Path) -> Result<KeyCheck, io::Error> {
+    let keys = srcinfo::read_srcinfo(pkg_dir)?.validpgpkeys;
+    let mut check = KeyCheck {
+        missing: keys.iter().filter(|key| !has_key(key)).cloned().collect(),
+        ..Default::default()
+    };
+    if let Some(record) = state::read_build_record(pkg_dir) {
+        (check.added, check.removed) = key_changes(&record.pgp_keys, &keys);
+        check.replaced = replaced_keys(&record.pgp_key_files, &key_file_hashes(pkg_dir, &keys));
+    }
+    Ok(check)
+}
+
+pub fn find_key_source(pkg_dir: &Path, keyring_dir: Option<&Path>, key: &str) -> KeySource {
+    let file_name = format!("{}.asc", key);
+    let repo_key = pkg_dir.join("keys").join("pgp").join(&file_name);
+    if repo_key.is_file() {
+        return KeySource::Repo(repo_key);
+    }
+    if let Some(keyring_dir) = keyring_dir {
+        let keyring_key = keyring_dir.join(&file_name);
+        if keyring_key.is_file() {
+            return KeySource::Keyring(keyring_key);
+        }
+    }
+    KeySource::Keyserver
+}
+
+// imports the key from the repo, the KeyringDir or the KeyServer of the config (gpg default if not
+// set) and checks, that the key is in the keyring afterwards. gpg is killed after the KeyTimeout
+pub fn import_key(pkg_dir: &Path, config: &Config, key: &str) -> Result<KeySource, String> {
+    let source = find_key_source(pkg_dir, config.keyring_dir().as_deref(), key);
+    let mut gpg = Command::new("gpg");
+    gpg.arg("--batch");
+    match &source {
+        KeySource::Repo(path) | KeySource::Keyring(path) => {
+            gpg.arg("--import").arg(path);
+        }
+        KeySource::Keyserver => {
+            if let Some(keyserver) = config.get("KeyServer") {
+                gpg.arg("--keyserver").arg(keyserver);
+            }
+            gpg.arg("--recv-keys").arg(key);
+        }
+    }
+    let timeout = config.timeout("KeyTimeout", Some(DEFAULT_KEY_TIMEOUT));
+    let output = process::run_output(&mut gpg, timeout)
+        .map_err(|err| format!("importing from {} failed: gpg: {}", source, err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "importing from {} failed: {}",
+            source,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    if !has_key(key) {
+        return Err(format!("{} doesn't contain the key", source));
+    }
+    Ok(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn find_key_source_test() {
+        let tmp_path = Path::new("/tmp/aur_helper_rs_test/find_key_source_test");
+        let _ = fs::remove_dir_all(tmp_path);
+        let pkg_dir = tmp_path.join("foo");
+        let keyring_dir = tmp_path.join("keyring");
+        fs::create_dir_all(pkg_dir.join("keys").join("pgp")).unwrap();
+        fs::create_dir_all(&keyring_dir).unwrap();
+        fs::write(pkg_dir.join("keys/pgp/AAAA.asc"), "").unwrap();
+        fs::write(keyring_dir.join("AAAA.asc"), "").unwrap();
+        fs::write(keyring_dir.join("BBBB.asc"), "").unwrap();
+
+        assert_eq!(
+            find_key_source(&pkg_dir, Some(&keyring_dir), "AAAA"),
+            KeySource::Repo(pkg_dir.join("keys/pgp/AAAA.asc"))
+        );
+        assert_eq!(
+            find_key_source(&pkg_dir, Some(&keyring_dir), "BBBB"),
+            KeySource::Keyring(keyring_dir.join("BBBB.asc"))
+        );
+        assert_eq!(
+            find_key_source(&pkg_dir, None, "BBBB"),
+            KeySource::Keyserver
+        );
+
+        let old = vec!["AAAA".to_string(), "BBBB".to_string()];
+        let new = vec!["AAAA".to_string(), "CCCC".to_string()];
+        assert_eq!(
+            key_changes(&old, &new),
+            (vec!["CCCC".to_string()], vec!["BBBB".to_string()])
+        );
+
+        let keys = vec!["AAAA".to_string(), "BBBB".to_string()];
+        let hashes = key_file_hashes(&pkg_dir, &keys);
+        assert_eq!(hashes.len(), 1);
+        assert_eq!(hashes[0].0, "AAAA");
+        assert!(replaced_keys(&hashes, &key_file_hashes(&pkg_dir, &keys)).is_empty());
+        fs::write(pkg_dir.join("keys/pgp/AAAA.asc"), "an other key").unwrap();
+        assert_eq!(
+            replaced_keys(&hashes, &key_file_hashes(&pkg_dir, &keys)),
+            vec!["AAAA".to_string()]
+        );
+
+        let _ = fs::remove_dir_all(tmp_path);
+    }
+}
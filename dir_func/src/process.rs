@@ -0,0 +1,279 @@
+use std::io::{self, IsTerminal, Read};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// set by Ctrl-C, the running child is killed and the remaining packages are cancelled
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// time between SIGTERM and SIGKILL for the process group of a killed child
+const KILL_GRACE: Duration = Duration::from_secs(5);
+
+const INTERRUPT_MSG: &[u8] = b"\nCancelling, press Ctrl-C again to quit immediately\n";
+
+extern "C" fn on_interrupt(_: libc::c_int) {
+    if CANCELLED.swap(true, Ordering::SeqCst) {
+        unsafe { libc::_exit(130) };
+    }
+    // only async-signal-safe calls in the handler
+    unsafe {
+        libc::write(
+            libc::STDERR_FILENO,
+            INTERRUPT_MSG.as_ptr() as *const libc::c_void,
+            INTERRUPT_MSG.len(),
+        )
+    };
+}
+
+// Ctrl-C cancels instead of killing aur_helper, so it can clean up and print a partial summary.
+// A second Ctrl-C quits immediately
+pub fn install_interrupt_handler() {
+    let handler = on_interrupt as extern "C" fn(libc::c_int);
+    unsafe { libc::signal(libc::SIGINT, handler as libc::sighandler_t) };
+}
+
+pub fn cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+pub fn cancel() {
+    CANCELLED.store(true, Ordering::SeqCst);
+}
+
+fn cancelled_error() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "cancelled")
+}
+
+// moves the process group to the foreground of the terminal, SIGTTOU is ignored, else a background
+// process would be stopped by it
+fn set_foreground(pgid: libc::pid_t) {
+    unsafe {
+        let old = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+        libc::tcsetpgrp(libc::STDIN_FILENO, pgid);
+        libc::signal(libc::SIGTTOU, old);
+    }
+}
+
+// SIGTERM to the process group of the child, SIGKILL if it doesn't exit in KILL_GRACE
+fn kill_group(child: &mut Child) {
+    let pgid = child.id() as libc::pid_t;
+    unsafe { libc::killpg(pgid, libc::SIGTERM) };
+    let start = Instant::now();
+    while start.elapsed() < KILL_GRACE {
+        if let Ok(Some(_)) = child.try_wait() {
+            // the rest of the group may still run
+            unsafe { libc::killpg(pgid, libc::SIGKILL) };
+            return;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    unsafe { libc::killpg(pgid, libc::SIGKILL) };
+    let _ = child.wait();
+}
+
+// waits for the child, kills its process group on a timeout or Ctrl-C
+fn wait_child(child: &mut Child, timeout: Option<Duration>) -> Result<ExitStatus, io::Error> {
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if cancelled() {
+            kill_group(child);
+            return Err(cancelled_error());
+        }
+        if let Some(timeout) = timeout {
+            if start.elapsed() >= timeout {
+                kill_group(child);
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("timed out after {}s", timeout.as_secs()),
+                ));
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+// the command as a shell command line, arguments with special characters are quoted
+pub fn command_line(cmd: &Command) -> String {
+    let quote = |arg: &str| {
+        if !arg.is_empty()
+            && arg
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c))
+        {
+            arg.to_string()
+        } else {
+            format!("'{}'", arg.replace('\'', "'\\''"))
+        }
+    };
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| quote(&arg.to_string_lossy()))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// spawns the command in its own process group, which gets the terminal, so Ctrl-C and prompts
+// reach it. Returns, if there is a terminal to give back with restore_foreground
+fn spawn_foreground(cmd: &mut Command) -> Result<(Child, bool), io::Error> {
+    let terminal = io::stdin().is_terminal();
+    if terminal {
+        unsafe {
+            cmd.pre_exec(|| {
+                libc::setpgid(0, 0);
+                set_foreground(libc::getpid());
+                Ok(())
+            })
+        };
+    } else {
+        cmd.process_group(0);
+    }
+    let child = cmd.spawn()?;
+    if terminal {
+        set_foreground(child.id() as libc::pid_t);
+    }
+    Ok((child, terminal))
+}
+
+fn restore_foreground(terminal: bool) {
+    if terminal {
+        set_foreground(unsafe { libc::getpgrp() });
+    }
+}
+
+// a command ended by Ctrl-C cancels the run
+fn check_interrupted(status: ExitStatus) -> Result<ExitStatus, io::Error> {
+    if status.signal() == Some(libc::SIGINT) || status.code() == Some(130) {
+        cancel();
+        return Err(cancelled_error());
+    }
+    Ok(status)
+}
+
+// runs an interactive command in its own process group, which gets the terminal, so Ctrl-C and
+// prompts reach it. Killed after the timeout, a command ended by Ctrl-C cancels the run
+pub fn run_status(cmd: &mut Command, timeout: Option<Duration>) -> Result<ExitStatus, io::Error> {
+    if cancelled() {
+        return Err(cancelled_error());
+    }
+    let (mut child, terminal) = spawn_foreground(cmd)?;
+    let status = wait_child(&mut child, timeout);
+    restore_foreground(terminal);
+    check_interrupted(status?)
+}
+
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+// runs a non interactive command in its own process group and collects the output, the group is
+// killed after the timeout or on Ctrl-C
+pub fn run_output(cmd: &mut Command, timeout: Option<Duration>) -> Result<Output, io::Error> {
+    if cancelled() {
+        return Err(cancelled_error());
+    }
+    let mut child = cmd
+        .process_group(0)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+    let status = wait_child(&mut child, timeout)?;
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+// run_output for commands, which may prompt on the terminal, like ssh for an unknown host key or
+// a key passphrase: stdin is kept and the process group gets the terminal like with run_status,
+// stdout and stderr are collected
+pub fn run_output_interactive(
+    cmd: &mut Command,
+    timeout: Option<Duration>,
+) -> Result<Output, io::Error> {
+    if cancelled() {
+        return Err(cancelled_error());
+    }
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let (mut child, terminal) = spawn_foreground(cmd)?;
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+    let status = wait_child(&mut child, timeout);
+    restore_foreground(terminal);
+    Ok(Output {
+        status: check_interrupted(status?)?,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_line_test() {
+        assert_eq!(
+            command_line(Command::new("makepkg").args(["--holdver", "CFLAGS=-O2 -g", "it's"])),
+            "makepkg --holdver 'CFLAGS=-O2 -g' 'it'\\''s'"
+        );
+    }
+
+    #[test]
+    fn run_output_test() {
+        let output = run_output(
+            Command::new("sh").args(["-c", "echo out; echo err >&2"]),
+            None,
+        )
+        .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+
+        // the background sleep holds the pipes open, so this only returns if the whole group is
+        // killed
+        let start = Instant::now();
+        let err = run_output(
+            Command::new("sh").args(["-c", "sleep 30 & sleep 30"]),
+            Some(Duration::from_millis(200)),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn run_output_interactive_test() {
+        let output = run_output_interactive(
+            Command::new("sh").args(["-c", "echo out; echo err >&2"]),
+            None,
+        )
+        .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+
+        let err = run_output_interactive(
+            Command::new("sh").args(["-c", "sleep 30"]),
+            Some(Duration::from_millis(200)),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
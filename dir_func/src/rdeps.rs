@@ -0,0 +1,197 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::pacman_db::DbPackage;
+use crate::srcinfo::{self, dep_name, SrcInfo};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepKind {
+    Depends,
+    MakeDepends,
+    CheckDepends,
+    OptDepends,
+}
+
+impl DepKind {
+    // the package can't be built or used without the dependency
+    pub fn required(&self) -> bool {
+        *self != DepKind::OptDepends
+    }
+}
+
+impl fmt::Display for DepKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DepKind::Depends => write!(f, "depends"),
+            DepKind::MakeDepends => write!(f, "makedepends"),
+            DepKind::CheckDepends => write!(f, "checkdepends"),
+            DepKind::OptDepends => write!(f, "optdepends"),
+        }
+    }
+}
+
+// a package depending on the queried one
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReverseDep {
+    // the package dir in the AUR dir, None for an installed package
+    pub dir: Option<PathBuf>,
+    pub pkgname: String,
+    // the dependency as declared, e.g. foo>=1.0
+    pub dep: String,
+    pub kind: DepKind,
+}
+
+// the .SRCINFO of the package dirs, dirs without one are left out
+pub fn read_srcinfos(dirs: &[PathBuf]) -> Vec<(PathBuf, SrcInfo)> {
+    dirs.iter()
+        .filter_map(|dir| {
+            srcinfo::read_srcinfo(dir)
+                .ok()
+                .map(|info| (dir.clone(), info))
+        })
+        .collect()
+}
+
+fn dir_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// the names, which satisfy a dependency on the package: its name and what it provides. A package
+// dir or pkgbase stands for all of its packages
+pub fn provided_names(
+    pkg: &str,
+    infos: &[(PathBuf, SrcInfo)],
+    installed: &[DbPackage],
+) -> Vec<String> {
+    let mut names: Vec<String> = vec![pkg.to_string()];
+    let mut add = |name: &str| {
+        let name = dep_name(name).to_string();
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    };
+    for (dir, info) in infos {
+        let whole_dir = dir_name(dir) == pkg || info.pkgbase == pkg;
+        for package in &info.packages {
+            if whole_dir || package.pkgname == pkg {
+                add(&package.pkgname);
+                package.provides.iter().for_each(|provide| add(provide));
+            }
+        }
+    }
+    for package in installed.iter().filter(|package| package.name == pkg) {
+        package.provides.iter().for_each(|provide| add(provide));
+    }
+    names
+}
+
+// the packages in the package dirs and the installed packages, which depend on one of the names.
+// The packages of the excluded dirs and the installed packages named in names are left out
+pub fn reverse_deps(
+    names: &[String],
+    infos: &[(PathBuf, SrcInfo)],
+    installed: &[DbPackage],
+    exclude: &[PathBuf],
+) -> Vec<ReverseDep> {
+    let mut rdeps: Vec<ReverseDep> = Vec::new();
+    let matches = |dep: &String| names.iter().any(|name| name == dep_name(dep));
+    for (dir, info) in infos.iter().filter(|(dir, _)| !exclude.contains(dir)) {
+        let mut push = |pkgname: &str, deps: &[String], kind: DepKind| {
+            for dep in deps.iter().filter(|dep| matches(dep)) {
+                rdeps.push(ReverseDep {
+                    dir: Some(dir.clone()),
+                    pkgname: pkgname.to_string(),
+                    dep: dep.clone(),
+                    kind,
+                });
+            }
+        };
+        for package in &info.packages {
+            push(&package.pkgname, &package.depends, DepKind::Depends);
+            push(&package.pkgname, &package.optdepends, DepKind::OptDepends);
+        }
+        push(&info.pkgbase, &info.makedepends, DepKind::MakeDepends);
+        push(&info.pkgbase, &info.checkdepends, DepKind::CheckDepends);
+    }
+    for package in installed
+        .iter()
+        .filter(|package| !names.contains(&package.name))
+    {
+        for (deps, kind) in [
+            (&package.depends, DepKind::Depends),
+            (&package.optdepends, DepKind::OptDepends),
+        ] {
+            for dep in deps.iter().filter(|dep| matches(dep)) {
+                rdeps.push(ReverseDep {
+                    dir: None,
+                    pkgname: package.name.clone(),
+                    dep: dep.clone(),
+                    kind,
+                });
+            }
+        }
+    }
+    rdeps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::srcinfo::parse_srcinfo;
+
+    #[test]
+    fn reverse_deps_test() {
+        let foo = parse_srcinfo(
+            "pkgbase = foo\n\tpkgver = 1\n\tpkgrel = 1\n\tprovides = libfoo.so=1-64\n\
+             pkgname = foo\npkgname = foo-docs\n\tprovides = foo-doc\n",
+        )
+        .unwrap();
+        let bar = parse_srcinfo(
+            "pkgbase = bar\n\tpkgver = 1\n\tpkgrel = 1\n\tmakedepends = foo-docs\n\
+             \tdepends = libfoo.so=1-64\n\toptdepends = foo: plugins\npkgname = bar\n",
+        )
+        .unwrap();
+        let infos = vec![
+            (PathBuf::from("/aur/foo"), foo),
+            (PathBuf::from("/aur/bar"), bar),
+        ];
+        let installed = vec![
+            DbPackage {
+                name: "baz".to_string(),
+                depends: vec!["foo>=1".to_string()],
+                ..Default::default()
+            },
+            DbPackage {
+                name: "foo".to_string(),
+                depends: vec!["glibc".to_string()],
+                ..Default::default()
+            },
+        ];
+
+        let names = provided_names("foo", &infos, &installed);
+        assert_eq!(names, vec!["foo", "libfoo.so", "foo-docs", "foo-doc"]);
+
+        let rdeps = reverse_deps(&names, &infos, &installed, &[PathBuf::from("/aur/foo")]);
+        let found: Vec<(&str, &str, DepKind)> = rdeps
+            .iter()
+            .map(|rdep| (rdep.pkgname.as_str(), rdep.dep.as_str(), rdep.kind))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("bar", "libfoo.so=1-64", DepKind::Depends),
+                ("bar", "foo: plugins", DepKind::OptDepends),
+                ("bar", "foo-docs", DepKind::MakeDepends),
+                ("baz", "foo>=1", DepKind::Depends),
+            ]
+        );
+        assert_eq!(rdeps[3].dir, None);
+
+        // only foo-docs of the split package
+        let names = provided_names("foo-docs", &infos, &installed);
+        assert_eq!(names, vec!["foo-docs", "foo-doc"]);
+        assert_eq!(reverse_deps(&names, &infos, &[], &[]).len(), 1);
+    }
+}
@@ -0,0 +1,250 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::time::Duration;
+use std::{env, io};
+
+use crate::{process, srcinfo};
+
+// the sandbox tool for the isolated builds
+pub const BWRAP: &str = "bwrap";
+
+// where makepkg builds inside the sandbox, a tmpfs
+const SANDBOX_BUILDDIR: &str = "/tmp/makepkg";
+
+// the files of the user, which are readable in the sandbox. Everything else in HOME is hidden, of
+// the GnuPG home only the public keyring and the trust db for the source verification
+const HOME_READ_ONLY: [&str; 5] = [
+    ".makepkg.conf",
+    ".config/pacman/makepkg.conf",
+    ".gnupg/pubring.kbx",
+    ".gnupg/pubring.gpg",
+    ".gnupg/trustdb.gpg",
+];
+
+// the bwrap arguments for running a command in the package dir: the root is read-only, HOME and
+// /run (ssh and gpg agent sockets) are replaced by empty tmpfs and only the package dir is writable.
+// The network is only shared with network = true
+pub fn bwrap_args(pkg_dir: &Path, home: Option<&Path>, network: bool) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "--ro-bind",
+        "/",
+        "/",
+        "--dev",
+        "/dev",
+        "--proc",
+        "/proc",
+        "--tmpfs",
+        "/tmp",
+        "--tmpfs",
+        "/run",
+        "--unshare-all",
+        "--die-with-parent",
+        "--new-session",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    if network {
+        args.push("--share-net".to_string());
+        // resolv.conf is often a link into the hidden /run
+        if let Ok(resolv_conf) = Path::new("/etc/resolv.conf").canonicalize() {
+            if resolv_conf.starts_with("/run") {
+                push_bind(&mut args, "--ro-bind", &resolv_conf);
+            }
+        }
+    }
+    if let Some(home) = home {
+        args.push("--tmpfs".to_string());
+        args.push(home.to_string_lossy().to_string());
+        for file in HOME_READ_ONLY {
+            let path = home.join(file);
+            if path.exists() {
+                push_bind(&mut args, "--ro-bind", &path);
+            }
+        }
+    }
+    push_bind(&mut args, "--bind", pkg_dir);
+    args.push("--chdir".to_string());
+    args.push(pkg_dir.to_string_lossy().to_string());
+    args.push("--unsetenv".to_string());
+    args.push("SSH_AUTH_SOCK".to_string());
+    // makepkg.conf can't move the outputs out of the writable package dir
+    for var in ["PKGDEST", "SRCDEST", "SRCPKGDEST", "LOGDEST"] {
+        args.push("--setenv".to_string());
+        args.push(var.to_string());
+        args.push(pkg_dir.to_string_lossy().to_string());
+    }
+    args.push("--setenv".to_string());
+    args.push("BUILDDIR".to_string());
+    args.push(SANDBOX_BUILDDIR.to_string());
+    args
+}
+
+fn push_bind(args: &mut Vec<String>, bind: &str, path: &Path) {
+    let path = path.to_string_lossy().to_string();
+    args.push(bind.to_string());
+    args.push(path.clone());
+    args.push(path);
+}
+
+// the dependencies of the .SRCINFO, which aren't satisfied by the installed packages
+fn missing_deps(pkg_dir: &Path) -> Result<Vec<String>, String> {
+    let deps = srcinfo::read_srcinfo(pkg_dir)
+        .map_err(|err| format!("couldn't read the .SRCINFO: {}", err))?
+        .build_deps();
+    if deps.is_empty() {
+        return Ok(Vec::new());
+    }
+    // pacman -T prints the unsatisfied dependencies and exits with 127 if there are some
+    let output = Command::new("pacman")
+        .arg("-T")
+        .args(&deps)
+        .output()
+        .map_err(|err| format!("couldn't call pacman: {}", err))?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|dep| dep.to_string())
+        .collect())
+}
+
+// installs the missing dependencies outside of the sandbox, where sudo works. Uses the .SRCINFO,
+// so the PKGBUILD isn't run outside of the sandbox, CALLS SUDO!
+fn install_deps(pkg_dir: &Path, commands: &mut Vec<String>) -> Result<(), String> {
+    let deps = missing_deps(pkg_dir)?;
+    if deps.is_empty() {
+        return Ok(());
+    }
+    let mut install = Command::new("sudo");
+    install
+        .arg("pacman")
+        .arg("-S")
+        .arg("--needed")
+        .arg("--asdeps")
+        .args(&deps);
+    let status = run_recorded(pkg_dir, &mut install, None, commands)
+        .map_err(|err| format!("pacman: {}", err))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("installing the dependencies failed: {}", status))
+    }
+}
+
+// prints the command and adds it to the commands of the build before running it
+pub(crate) fn run_recorded(
+    pkg_dir: &Path,
+    cmd: &mut Command,
+    timeout: Option<Duration>,
+    commands: &mut Vec<String>,
+) -> Result<ExitStatus, io::Error> {
+    let line = process::command_line(cmd);
+    println!("{}: running {}", pkg_dir.display(), line);
+    commands.push(line);
+    process::run_status(cmd, timeout)
+}
+
+fn run_sandboxed(
+    pkg_dir: &Path,
+    network: bool,
+    makepkg_args: &[String],
+    timeout: Option<Duration>,
+    commands: &mut Vec<String>,
+) -> Result<ExitStatus, String> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    // the binds need absolute paths
+    let pkg_dir = pkg_dir
+        .canonicalize()
+        .map_err(|err| format!("couldn't resolve {}: {}", pkg_dir.display(), err))?;
+    run_recorded(
+        &pkg_dir,
+        Command::new(BWRAP)
+            .args(bwrap_args(&pkg_dir, home.as_deref(), network))
+            .arg("makepkg")
+            .args(makepkg_args),
+        timeout,
+        commands,
+    )
+    .map_err(|err| match err.kind() {
+        io::ErrorKind::NotFound => format!("couldn't execute {}, is bubblewrap installed?", BWRAP),
+        _ => format!("makepkg in the sandbox: {}", err),
+    })
+}
+
+// the makepkg arguments without -s/--syncdeps, for builds where the dependencies are installed
+// before
+pub(crate) fn without_syncdeps(makepkg_args: &[String]) -> Vec<String> {
+    makepkg_args
+        .iter()
+        .filter(|arg| *arg != "-s" && *arg != "--syncdeps")
+        .cloned()
+        .collect()
+}
+
+// builds the package with makepkg in a bubblewrap sandbox: the sources are downloaded with network,
+// the build runs without network in a tmpfs. Dependencies are installed before from the .SRCINFO,
+// so -s/--syncdeps is dropped from the arguments. The timeout applies to the download and the build.
+// The commands actually run are added to commands, CALLS SUDO!
+pub fn makepkg(
+    pkg_dir: &Path,
+    makepkg_args: &[String],
+    timeout: Option<Duration>,
+    commands: &mut Vec<String>,
+) -> Result<ExitStatus, String> {
+    install_deps(pkg_dir, commands)?;
+    let download = run_sandboxed(
+        pkg_dir,
+        true,
+        &["--verifysource".to_string()],
+        timeout,
+        commands,
+    )?;
+    if !download.success() {
+        return Err(format!("downloading the sources failed: {}", download));
+    }
+    let mut build_args = without_syncdeps(makepkg_args);
+    // vcs sources can't be updated without network
+    build_args.push("--holdver".to_string());
+    run_sandboxed(pkg_dir, false, &build_args, timeout, commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn bwrap_args_test() {
+        let tmp_path = Path::new("/tmp/aur_helper_rs_test/bwrap_args_test");
+        let _ = fs::remove_dir_all(tmp_path);
+        let home = tmp_path.join("home");
+        let pkg_dir = home.join("aur").join("foo");
+        fs::create_dir_all(home.join(".ssh")).unwrap();
+        fs::create_dir_all(home.join(".gnupg").join("private-keys-v1.d")).unwrap();
+        fs::write(home.join(".gnupg").join("pubring.kbx"), "").unwrap();
+        fs::create_dir_all(&pkg_dir).unwrap();
+
+        let args = bwrap_args(&pkg_dir, Some(&home), false);
+        let joined = args.join(" ");
+        let home_str = home.to_string_lossy();
+        let pkg_str = pkg_dir.to_string_lossy();
+        assert!(joined.starts_with("--ro-bind / / "));
+        assert!(!args.contains(&"--share-net".to_string()));
+        assert!(joined.contains(&format!("--tmpfs {} ", home_str)));
+        assert!(joined.contains(&format!(
+            "--ro-bind {0}/.gnupg/pubring.kbx {0}/.gnupg/pubring.kbx",
+            home_str
+        )));
+        assert!(!joined.contains(&format!("{}/.gnupg ", home_str)));
+        assert!(!joined.contains("private-keys-v1.d"));
+        assert!(!joined.contains(".ssh"));
+        // the package dir is mounted after the home tmpfs, otherwise it would be hidden
+        let home_pos = joined.find(&format!("--tmpfs {} ", home_str)).unwrap();
+        let pkg_pos = joined.find(&format!("--bind {0} {0}", pkg_str)).unwrap();
+        assert!(home_pos < pkg_pos);
+        assert!(joined.contains("--setenv BUILDDIR /tmp/makepkg"));
+
+        assert!(bwrap_args(&pkg_dir, Some(&home), true).contains(&"--share-net".to_string()));
+
+        let _ = fs::remove_dir_all(tmp_path);
+    }
+}
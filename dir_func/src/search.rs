@@ -0,0 +1,187 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use raur::{Raur, SearchBy};
+
+// the fields of the RPC search, which can be searched by
+pub const SEARCH_FIELDS: [&str; 10] = [
+    "name",
+    "name-desc",
+    "maintainer",
+    "depends",
+    "makedepends",
+    "optdepends",
+    "checkdepends",
+    "provides",
+    "keywords",
+    "submitter",
+];
+
+pub fn parse_search_by(field: &str) -> Result<SearchBy, String> {
+    match field {
+        "name" => Ok(SearchBy::Name),
+        "name-desc" => Ok(SearchBy::NameDesc),
+        "maintainer" => Ok(SearchBy::Maintainer),
+        "depends" => Ok(SearchBy::Depends),
+        "makedepends" => Ok(SearchBy::MakeDepends),
+        "optdepends" => Ok(SearchBy::OptDepends),
+        "checkdepends" => Ok(SearchBy::CheckDepends),
+        "provides" => Ok(SearchBy::Provides),
+        "keywords" => Ok(SearchBy::Keywords),
+        "submitter" => Ok(SearchBy::Submitter),
+        _ => Err(format!("unknown search field '{}'", field)),
+    }
+}
+
+// the order of the search results, the numbers are sorted descending, the names ascending
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortBy {
+    Votes,
+    Popularity,
+    Modified,
+    Name,
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "votes" => Ok(Self::Votes),
+            "popularity" => Ok(Self::Popularity),
+            "modified" => Ok(Self::Modified),
+            "name" => Ok(Self::Name),
+            _ => Err(format!("unknown sort key '{}'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    pub by: SearchBy,
+    // None keeps the order of the RPC
+    pub sort: Option<SortBy>,
+    pub limit: Option<usize>,
+}
+
+// the term is in the name or, for name-desc, in the description
+fn matches_term(pkg: &raur::Package, term: &str, by: SearchBy) -> bool {
+    let term = term.to_lowercase();
+    pkg.name.to_lowercase().contains(&term)
+        || (by == SearchBy::NameDesc
+            && pkg
+                .description
+                .as_ref()
+                .is_some_and(|desc| desc.to_lowercase().contains(&term)))
+}
+
+// the packages of the first result, which are in all other results
+fn intersect(mut results: Vec<Vec<raur::Package>>) -> Vec<raur::Package> {
+    if results.is_empty() {
+        return Vec::new();
+    }
+    let first = results.remove(0);
+    let others: Vec<HashSet<String>> = results
+        .into_iter()
+        .map(|pkgs| pkgs.into_iter().map(|pkg| pkg.name).collect())
+        .collect();
+    first
+        .into_iter()
+        .filter(|pkg| others.iter().all(|names| names.contains(&pkg.name)))
+        .collect()
+}
+
+pub fn sort_packages(pkgs: &mut [raur::Package], sort: SortBy) {
+    pkgs.sort_by(|a, b| match sort {
+        SortBy::Votes => b.num_votes.cmp(&a.num_votes),
+        SortBy::Popularity => b
+            .popularity
+            .partial_cmp(&a.popularity)
+            .unwrap_or(Ordering::Equal),
+        SortBy::Modified => b.last_modified.cmp(&a.last_modified),
+        SortBy::Name => a.name.cmp(&b.name),
+    });
+}
+
+// sorts and limits the packages found for all terms
+fn finish(mut pkgs: Vec<raur::Package>, options: &SearchOptions) -> Vec<raur::Package> {
+    if let Some(sort) = options.sort {
+        sort_packages(&mut pkgs, sort);
+    }
+    if let Some(limit) = options.limit {
+        pkgs.truncate(limit);
+    }
+    pkgs
+}
+
+// searches the AUR for packages matching all terms like yay: name and name-desc request the longest
+// term and filter the results by the others, the exact fields like depends request every term and
+// intersect the results
+pub async fn search(
+    terms: &[String],
+    options: &SearchOptions,
+) -> Result<Vec<raur::Package>, raur::Error> {
+    let raur_handler = raur::Handle::new();
+    if matches!(options.by, SearchBy::Name | SearchBy::NameDesc) {
+        let Some(longest) = terms.iter().max_by_key(|term| term.len()) else {
+            return Ok(Vec::new());
+        };
+        let pkgs = raur_handler
+            .search_by(longest, options.by)
+            .await?
+            .into_iter()
+            .filter(|pkg| terms.iter().all(|term| matches_term(pkg, term, options.by)))
+            .collect();
+        return Ok(finish(pkgs, options));
+    }
+    let mut results: Vec<Vec<raur::Package>> = Vec::new();
+    for term in terms {
+        results.push(raur_handler.search_by(term, options.by).await?);
+    }
+    Ok(finish(intersect(results), options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pkg(name: &str, votes: u32, description: &str) -> raur::Package {
+        raur::Package {
+            name: name.to_string(),
+            num_votes: votes,
+            description: Some(description.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn search_results_test() {
+        let foo = pkg("foo-git", 3, "A Wayland launcher");
+        assert!(matches_term(&foo, "wayland", SearchBy::NameDesc));
+        assert!(!matches_term(&foo, "wayland", SearchBy::Name));
+        assert!(matches_term(&foo, "FOO", SearchBy::Name));
+
+        let found = intersect(vec![
+            vec![foo.clone(), pkg("bar", 10, ""), pkg("baz", 1, "")],
+            vec![pkg("baz", 1, ""), foo.clone()],
+        ]);
+        let names: Vec<&str> = found.iter().map(|pkg| pkg.name.as_str()).collect();
+        assert_eq!(names, vec!["foo-git", "baz"]);
+
+        let options = SearchOptions {
+            sort: Some(SortBy::Votes),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let pkgs = finish(vec![foo, pkg("bar", 10, ""), pkg("baz", 1, "")], &options);
+        let names: Vec<&str> = pkgs.iter().map(|pkg| pkg.name.as_str()).collect();
+        assert_eq!(names, vec!["bar", "foo-git"]);
+
+        assert_eq!("modified".parse(), Ok(SortBy::Modified));
+        assert!("size".parse::<SortBy>().is_err());
+        for field in SEARCH_FIELDS {
+            assert!(parse_search_by(field).is_ok());
+        }
+    }
+}
@@ -0,0 +1,77 @@
+// parses a selection of numbered results like yay: numbers and ranges separated by spaces or
+// commas, e.g. "1 3 5-7". Entries starting with ^ are excluded, "^4" alone selects everything
+// except 4. Returns the 0-based indices in the order of the results, empty input selects nothing
+pub fn parse_selection(input: &str, count: usize) -> Result<Vec<usize>, String> {
+    let mut included: Vec<bool> = vec![false; count];
+    let mut excluded: Vec<bool> = vec![false; count];
+    let mut only_exclusions = true;
+    let mut any = false;
+    for token in input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+    {
+        any = true;
+        let (exclude, range) = match token.strip_prefix('^') {
+            Some(range) => (true, range),
+            None => (false, token),
+        };
+        let (start, end) = parse_range(range, count)?;
+        let marks = if exclude {
+            &mut excluded
+        } else {
+            only_exclusions = false;
+            &mut included
+        };
+        for mark in &mut marks[start - 1..end] {
+            *mark = true;
+        }
+    }
+    if !any {
+        return Ok(Vec::new());
+    }
+    Ok((0..count)
+        .filter(|&index| (only_exclusions || included[index]) && !excluded[index])
+        .collect())
+}
+
+// a number or a range like 5-7 of the 1-based result numbers
+fn parse_range(range: &str, count: usize) -> Result<(usize, usize), String> {
+    let parse = |number: &str| {
+        let number: usize = number
+            .parse()
+            .map_err(|_| format!("'{}' isn't a number or a range", range))?;
+        if number == 0 || number > count {
+            return Err(format!("{} is out of range 1-{}", number, count));
+        }
+        Ok(number)
+    };
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (parse(start)?, parse(end)?),
+        None => {
+            let number = parse(range)?;
+            (number, number)
+        }
+    };
+    if start > end {
+        return Err(format!("'{}' is an empty range", range));
+    }
+    Ok((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_selection_test() {
+        assert_eq!(parse_selection("1 3 5-7", 8).unwrap(), vec![0, 2, 4, 5, 6]);
+        assert_eq!(parse_selection("^4", 5).unwrap(), vec![0, 1, 2, 4]);
+        assert_eq!(parse_selection("1-5,^2-3", 6).unwrap(), vec![0, 3, 4]);
+        assert_eq!(parse_selection("2 2 1", 3).unwrap(), vec![0, 1]);
+        assert!(parse_selection("  ", 3).unwrap().is_empty());
+        assert!(parse_selection("4", 3).is_err());
+        assert!(parse_selection("0", 3).is_err());
+        assert!(parse_selection("3-1", 3).is_err());
+        assert!(parse_selection("foo", 3).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::{fs, io};
+
+// a package section of a .SRCINFO, the fields are inherited from the pkgbase if not overwritten
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SrcInfoPackage {
+    pub pkgname: String,
+    pub arch: Vec<String>,
+    pub depends: Vec<String>,
+    pub optdepends: Vec<String>,
+    pub provides: Vec<String>,
+}
+
+// the parsed content of a .SRCINFO file, arch specific fields of the current arch are merged
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SrcInfo {
+    pub pkgbase: String,
+    pub pkgver: String,
+    pub pkgrel: String,
+    pub epoch: Option<String>,
+    pub arch: Vec<String>,
+    pub depends: Vec<String>,
+    pub makedepends: Vec<String>,
+    pub checkdepends: Vec<String>,
+    pub optdepends: Vec<String>,
+    pub provides: Vec<String>,
+    pub validpgpkeys: Vec<String>,
+    pub packages: Vec<SrcInfoPackage>,
+}
+
+impl SrcInfo {
+    // version as pacman shows it: [epoch:]pkgver-pkgrel
+    pub fn full_version(&self) -> String {
+        match &self.epoch {
+            Some(epoch) if epoch != "0" => format!("{}:{}-{}", epoch, self.pkgver, self.pkgrel),
+            _ => format!("{}-{}", self.pkgver, self.pkgrel),
+        }
+    }
+
+    // the depends, makedepends and checkdepends of the base and the split packages, needed to build
+    pub fn build_deps(&self) -> Vec<String> {
+        let mut deps: Vec<String> = Vec::new();
+        let all = self
+            .depends
+            .iter()
+            .chain(&self.makedepends)
+            .chain(&self.checkdepends)
+            .chain(self.packages.iter().flat_map(|pkg| &pkg.depends));
+        for dep in all {
+            if !deps.contains(dep) {
+                deps.push(dep.clone());
+            }
+        }
+        deps
+    }
+
+    pub fn pkgnames(&self) -> Vec<String> {
+        self.packages.iter().map(|p| p.pkgname.clone()).collect()
+    }
+
+    // the file names of the packages makepkg builds for this version, pkgext like '.pkg.tar.zst'
+    pub fn artifact_names(&self, pkgext: &str) -> Vec<String> {
+        self.packages
+            .iter()
+            .map(|pkg| {
+                let arch = if pkg.arch.iter().any(|arch| arch == "any") {
+                    "any"
+                } else {
+                    std::env::consts::ARCH
+                };
+                format!("{}-{}-{}{}", pkg.pkgname, self.full_version(), arch, pkgext)
+            })
+            .collect()
+    }
+}
+
+// reads the .SRCINFO in the package directory
+pub fn read_srcinfo(dir: &Path) -> Result<SrcInfo, io::Error> {
+    let content = fs::read_to_string(dir.join(".SRCINFO"))?;
+    parse_srcinfo(&content)
+}
+
+// strips an arch suffix like 'depends_x86_64', returns None for foreign architectures
+fn strip_arch(key: &str) -> Option<&str> {
+    match key.split_once('_') {
+        Some((base, arch)) if arch == std::env::consts::ARCH => Some(base),
+        Some((_, _)) => None,
+        None => Some(key),
+    }
+}
+
+pub fn parse_srcinfo(content: &str) -> Result<SrcInfo, io::Error> {
+    let mut info = SrcInfo::default();
+    // keys already overwritten in the current pkgname section
+    let mut overwritten: HashSet<String> = HashSet::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = match line.split_once(" = ") {
+            Some((key, value)) => (key.trim(), value.trim().to_string()),
+            None => match line.strip_suffix(" =") {
+                Some(key) => (key.trim(), String::new()),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Malformed .SRCINFO line: '{}'", line),
+                    ))
+                }
+            },
+        };
+
+        if key == "pkgname" {
+            overwritten.clear();
+            info.packages.push(SrcInfoPackage {
+                pkgname: value,
+                arch: info.arch.clone(),
+                depends: info.depends.clone(),
+                optdepends: info.optdepends.clone(),
+                provides: info.provides.clone(),
+            });
+            continue;
+        }
+        let key = match strip_arch(key) {
+            Some(key) => key,
+            None => continue,
+        };
+
+        match info.packages.last_mut() {
+            None => match key {
+                "pkgbase" => info.pkgbase = value,
+                "pkgver" => info.pkgver = value,
+                "pkgrel" => info.pkgrel = value,
+                "epoch" => info.epoch = Some(value),
+                "arch" => info.arch.push(value),
+                "depends" => info.depends.push(value),
+                "makedepends" => info.makedepends.push(value),
+                "checkdepends" => info.checkdepends.push(value),
+                "optdepends" => info.optdepends.push(value),
+                "provides" => info.provides.push(value),
+                "validpgpkeys" => info.validpgpkeys.push(value),
+                _ => {}
+            },
+            Some(pkg) => {
+                let field = match key {
+                    "arch" => &mut pkg.arch,
+                    "depends" => &mut pkg.depends,
+                    "optdepends" => &mut pkg.optdepends,
+                    "provides" => &mut pkg.provides,
+                    _ => continue,
+                };
+                if overwritten.insert(key.to_string()) {
+                    field.clear();
+                }
+                // an empty value removes the inherited entries
+                if !value.is_empty() {
+                    field.push(value);
+                }
+            }
+        }
+    }
+
+    if info.pkgbase.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            ".SRCINFO contains no pkgbase",
+        ));
+    }
+    Ok(info)
+}
+
+// strips the version requirement from a dependency like 'foo>=1.0' or 'foo: optional reason'
+pub fn dep_name(dep: &str) -> &str {
+    let end = dep.find(['<', '>', '=', ':']).unwrap_or(dep.len());
+    dep[..end].trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRCINFO: &str = "pkgbase = foo
+\tpkgdesc = a test package
+\tpkgver = 1.2.3
+\tpkgrel = 2
+\tepoch = 1
+\tarch = x86_64
+\tarch = aarch64
+\tdepends = glibc
+\tdepends = bar>=2.0
+\tdepends_x86_64 = lib32-bar
+\tdepends_armv7h = armlib
+\tmakedepends = git
+\tprovides = foo-common
+\tvalidpgpkeys = ABCDEF0123456789
+
+pkgname = foo
+
+pkgname = foo-headers
+\tdepends =
+\tprovides = foo-devel
+";
+
+    #[test]
+    fn parse_srcinfo_test() {
+        let info = parse_srcinfo(SRCINFO).unwrap();
+        assert_eq!(info.pkgbase, "foo");
+        assert_eq!(info.full_version(), "1:1.2.3-2");
+        assert_eq!(info.pkgnames(), vec!["foo", "foo-headers"]);
+        assert_eq!(
+            info.artifact_names(".pkg.tar.zst")[1],
+            format!(
+                "foo-headers-1:1.2.3-2-{}.pkg.tar.zst",
+                std::env::consts::ARCH
+            )
+        );
+        assert_eq!(info.makedepends, vec!["git"]);
+        // the depends of the split package foo are the same as the ones of the base
+        let mut build_deps = info.depends.clone();
+        build_deps.push("git".to_string());
+        assert_eq!(info.build_deps(), build_deps);
+        assert_eq!(info.validpgpkeys, vec!["ABCDEF0123456789"]);
+        assert!(!info.depends.contains(&"armlib".to_string()));
+
+        let foo = &info.packages[0];
+        assert_eq!(foo.depends, info.depends);
+        assert_eq!(foo.provides, vec!["foo-common"]);
+
+        let headers = &info.packages[1];
+        assert!(headers.depends.is_empty());
+        assert_eq!(headers.provides, vec!["foo-devel"]);
+    }
+
+    #[test]
+    fn dep_name_test() {
+        assert_eq!(dep_name("bar>=2.0"), "bar");
+        assert_eq!(dep_name("python-foo: for the foo plugin"), "python-foo");
+        assert_eq!(dep_name("glibc"), "glibc");
+    }
+}
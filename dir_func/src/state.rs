@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::{fs, io};
+
+// directory in the AUR dir for the state of aur_helper, hidden dirs are skipped by get_dirs
+pub const STATE_DIR: &str = ".aur_helper";
+
+pub fn state_dir(aur_path: &Path) -> PathBuf {
+    aur_path.join(STATE_DIR)
+}
+
+// the AUR dir containing the package dir
+pub fn aur_path_of(pkg_dir: &Path) -> &Path {
+    pkg_dir.parent().unwrap_or(Path::new("."))
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// result of the last build of a package, stored in .aur_helper/builds/<pkg_dir>
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BuildRecord {
+    pub time: u64,
+    pub success: bool,
+    pub status: String,
+    // the makepkg arguments of the build
+    pub makepkg_args: Vec<String>,
+    // the commands run for the build, differ from makepkg with the args in the sandbox
+    pub commands: Vec<String>,
+    // the validpgpkeys of the .SRCINFO at the build
+    pub pgp_keys: Vec<String>,
+    // the keys shipped in keys/pgp of the repo with the hash of their file
+    pub pgp_key_files: Vec<(String, String)>,
+}
+
+fn build_record_path(pkg_dir: &Path) -> PathBuf {
+    state_dir(aur_path_of(pkg_dir))
+        .join("builds")
+        .join(pkg_dir.file_name().unwrap_or_default())
+}
+
+pub fn write_build_record(pkg_dir: &Path, record: &BuildRecord) -> Result<(), io::Error> {
+    let path = build_record_path(pkg_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut content = format!(
+        "time = {}\nsuccess = {}\nstatus = {}\nmakepkg_args = {}\npgp_keys = {}\n",
+        record.time,
+        record.success,
+        record.status,
+        record.makepkg_args.join(" "),
+        record.pgp_keys.join(" ")
+    );
+    for command in &record.commands {
+        content.push_str(&format!("command = {}\n", command));
+    }
+    for (key, hash) in &record.pgp_key_files {
+        content.push_str(&format!("pgp_key_file = {} {}\n", key, hash));
+    }
+    fs::write(path, content)
+}
+
+pub fn read_build_record(pkg_dir: &Path) -> Option<BuildRecord> {
+    let content = fs::read_to_string(build_record_path(pkg_dir)).ok()?;
+    let mut record = BuildRecord::default();
+    for line in content.lines() {
+        match line.split_once(" = ") {
+            Some(("time", value)) => record.time = value.parse().ok()?,
+            Some(("success", value)) => record.success = value == "true",
+            Some(("status", value)) => record.status = value.to_string(),
+            Some(("makepkg_args", value)) => {
+                record.makepkg_args = value.split_whitespace().map(|a| a.to_string()).collect()
+            }
+            Some(("command", value)) => record.commands.push(value.to_string()),
+            Some(("pgp_key_file", value)) => {
+                if let Some((key, hash)) = value.split_once(' ') {
+                    record
+                        .pgp_key_files
+                        .push((key.to_string(), hash.to_string()));
+                }
+            }
+            Some(("pgp_keys", value)) => {
+                record.pgp_keys = value.split_whitespace().map(|k| k.to_string()).collect()
+            }
+            _ => {}
+        }
+    }
+    Some(record)
+}
+
+fn requested_path(pkg_dir: &Path) -> PathBuf {
+    state_dir(aur_path_of(pkg_dir))
+        .join("requested")
+        .join(pkg_dir.file_name().unwrap_or_default())
+}
+
+// the packages of a split package the user asked for, stored in .aur_helper/requested/<pkg_dir>.
+// Empty if the package wasn't downloaded by a package name
+pub fn read_requested(pkg_dir: &Path) -> Vec<String> {
+    fs::read_to_string(requested_path(pkg_dir))
+        .map(|content| content.lines().map(|name| name.to_string()).collect())
+        .unwrap_or_default()
+}
+
+// adds the names to the requested packages of the package dir
+pub fn add_requested(pkg_dir: &Path, names: &[String]) -> Result<(), io::Error> {
+    let mut requested = read_requested(pkg_dir);
+    for name in names {
+        if !requested.contains(name) {
+            requested.push(name.clone());
+        }
+    }
+    let path = requested_path(pkg_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, requested.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_record_test() {
+        let aur_path = Path::new("/tmp/aur_helper_rs_test/build_record_test");
+        let _ = fs::remove_dir_all(aur_path);
+        let pkg_dir = aur_path.join("foo");
+
+        assert!(read_build_record(&pkg_dir).is_none());
+        let record = BuildRecord {
+            time: 42,
+            success: false,
+            status: "exit status: 4".to_string(),
+            makepkg_args: vec!["-s".to_string(), "--nocheck".to_string()],
+            pgp_keys: vec!["ABCDEF0123456789".to_string()],
+            pgp_key_files: vec![(
+                "ABCDEF0123456789".to_string(),
+                "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391".to_string(),
+            )],
+            commands: vec![
+                "sudo pacman -S --needed --asdeps cmake".to_string(),
+                "bwrap --ro-bind / / makepkg --holdver".to_string(),
+            ],
+        };
+        write_build_record(&pkg_dir, &record).unwrap();
+        assert_eq!(read_build_record(&pkg_dir), Some(record));
+
+        assert!(read_requested(&pkg_dir).is_empty());
+        add_requested(&pkg_dir, &["foo-headers".to_string()]).unwrap();
+        add_requested(&pkg_dir, &["foo".to_string(), "foo-headers".to_string()]).unwrap();
+        assert_eq!(read_requested(&pkg_dir), vec!["foo-headers", "foo"]);
+
+        let _ = fs::remove_dir_all(aur_path);
+    }
+}
@@ -0,0 +1,271 @@
+use std::env;
+use std::io::{self, IsTerminal};
+
+// between the columns of a table
+const SEPARATOR: &str = " | ";
+
+// narrower columns aren't readable, the table is stacked instead
+const MIN_COLUMN_WIDTH: usize = 12;
+
+// how cells wider than their column are shown
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Overflow {
+    // cut with an ellipsis
+    #[default]
+    Truncate,
+    // continued on the next lines
+    Wrap,
+}
+
+// the width of the terminal, $COLUMNS if set. Output, which isn't a terminal, has no limit
+pub fn terminal_width() -> Option<usize> {
+    if let Some(columns) = env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let res = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    if res == 0 && size.ws_col > 0 {
+        Some(size.ws_col as usize)
+    } else {
+        Some(80)
+    }
+}
+
+fn width_of(text: &str) -> usize {
+    text.chars().count()
+}
+
+// the text cut to the width, the last character replaced by an ellipsis
+pub fn truncate(text: &str, width: usize) -> String {
+    if width_of(text) <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut cut: String = text.chars().take(width - 1).collect();
+    cut.push('…');
+    cut
+}
+
+// the text split into lines of the width, at the width if there is no better place like a '-'
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    if width == 0 {
+        return vec![String::new()];
+    }
+    let mut lines: Vec<String> = Vec::new();
+    let mut rest: Vec<char> = text.chars().collect();
+    while rest.len() > width {
+        let split = rest[..width]
+            .iter()
+            .rposition(|c| *c == ' ' || *c == '-')
+            .map(|pos| pos + 1)
+            .filter(|pos| *pos >= width / 3)
+            .unwrap_or(width);
+        lines.push(
+            rest[..split]
+                .iter()
+                .collect::<String>()
+                .trim_end()
+                .to_string(),
+        );
+        rest = rest[split..].to_vec();
+    }
+    lines.push(rest.into_iter().collect());
+    lines
+}
+
+fn fit(text: &str, width: usize, overflow: Overflow) -> Vec<String> {
+    match overflow {
+        Overflow::Truncate => vec![truncate(text, width)],
+        Overflow::Wrap => wrap(text, width),
+    }
+}
+
+// the widths of the columns in the total width: narrow columns keep their width, the space left is
+// shared by the wide ones. None if a column would be narrower than MIN_COLUMN_WIDTH
+fn column_widths(natural: &[usize], total: usize) -> Option<Vec<usize>> {
+    let separators = SEPARATOR.len() * natural.len().saturating_sub(1);
+    let sum: usize = natural.iter().sum();
+    if sum + separators <= total {
+        return Some(natural.to_vec());
+    }
+    let mut available = total.checked_sub(separators)?;
+    let mut widths: Vec<Option<usize>> = vec![None; natural.len()];
+    loop {
+        let open = widths.iter().filter(|width| width.is_none()).count();
+        if open == 0 {
+            break;
+        }
+        let share = available / open;
+        let mut fixed = false;
+        for (width, natural) in widths.iter_mut().zip(natural) {
+            if width.is_none() && *natural <= share {
+                *width = Some(*natural);
+                available -= natural;
+                fixed = true;
+            }
+        }
+        if !fixed {
+            if share < MIN_COLUMN_WIDTH {
+                return None;
+            }
+            let mut extra = available - share * open;
+            for width in widths.iter_mut().filter(|width| width.is_none()) {
+                *width = Some(share + usize::from(extra > 0));
+                extra = extra.saturating_sub(1);
+            }
+        }
+    }
+    Some(widths.into_iter().map(|width| width.unwrap_or(0)).collect())
+}
+
+// renders the columns side by side in the width, or stacked with the header above each column if
+// the width is too small. No width renders the columns at their natural width
+pub fn render_columns(
+    headers: &[&str],
+    columns: &[Vec<String>],
+    width: Option<usize>,
+    overflow: Overflow,
+) -> Vec<String> {
+    let natural: Vec<usize> = headers
+        .iter()
+        .zip(columns)
+        .map(|(header, cells)| {
+            cells
+                .iter()
+                .map(|cell| width_of(cell))
+                .chain([width_of(header)])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let widths = match width {
+        None => Some(natural),
+        Some(width) => column_widths(&natural, width),
+    };
+    let Some(widths) = widths else {
+        return render_stacked(headers, columns, width.unwrap_or(usize::MAX), overflow);
+    };
+
+    let rows = columns.iter().map(|cells| cells.len()).max().unwrap_or(0);
+    let mut lines: Vec<String> = Vec::new();
+    let header_cells: Vec<Vec<String>> = headers
+        .iter()
+        .zip(&widths)
+        .map(|(header, width)| vec![truncate(header, *width)])
+        .collect();
+    push_row(&mut lines, &header_cells, &widths);
+    for row in 0..rows {
+        let cells: Vec<Vec<String>> = columns
+            .iter()
+            .zip(&widths)
+            .map(|(cells, width)| match cells.get(row) {
+                Some(cell) => fit(cell, *width, overflow),
+                None => Vec::new(),
+            })
+            .collect();
+        push_row(&mut lines, &cells, &widths);
+    }
+    lines
+}
+
+// pushes the lines of a row, the cells can have multiple lines
+fn push_row(lines: &mut Vec<String>, cells: &[Vec<String>], widths: &[usize]) {
+    let height = cells
+        .iter()
+        .map(|cell| cell.len())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    for line in 0..height {
+        let mut parts: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| {
+                let text = cell.get(line).map(|text| text.as_str()).unwrap_or("");
+                format!("{: <width$}", text, width = width)
+            })
+            .collect();
+        // no separators after the last cell with text
+        while parts.len() > 1 && parts.last().is_some_and(|part| part.trim().is_empty()) {
+            parts.pop();
+        }
+        lines.push(parts.join(SEPARATOR).trim_end().to_string());
+    }
+}
+
+fn render_stacked(
+    headers: &[&str],
+    columns: &[Vec<String>],
+    width: usize,
+    overflow: Overflow,
+) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for (header, cells) in headers.iter().zip(columns) {
+        if cells.is_empty() {
+            continue;
+        }
+        lines.push(truncate(header, width));
+        for cell in cells {
+            for line in fit(cell, width.saturating_sub(2), overflow) {
+                lines.push(format!("  {}", line));
+            }
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(|cell| cell.to_string()).collect()
+    }
+
+    #[test]
+    fn render_columns_test() {
+        assert_eq!(truncate("python-sphinx", 8), "python-…");
+        assert_eq!(truncate("glibc", 8), "glibc");
+        assert_eq!(
+            wrap("python-sphinxcontrib-applehelp>=1.0", 16),
+            vec!["python-", "sphinxcontrib-", "applehelp>=1.0"]
+        );
+
+        let headers = ["[Runtime]", "[Make]"];
+        let columns = vec![
+            cells(&["glibc", "python-sphinxcontrib-applehelp>=1.0"]),
+            cells(&["git"]),
+        ];
+        let natural = render_columns(&headers, &columns, None, Overflow::Truncate);
+        assert_eq!(natural[0], "[Runtime]                           | [Make]");
+        assert_eq!(natural[2], "python-sphinxcontrib-applehelp>=1.0");
+
+        // the wide column shrinks, the narrow one keeps its width
+        let narrow = render_columns(&headers, &columns, Some(30), Overflow::Truncate);
+        assert!(narrow.iter().all(|line| width_of(line) <= 30));
+        assert_eq!(narrow[1], "glibc                 | git");
+        assert_eq!(narrow[2], "python-sphinxcontrib…");
+
+        let wrapped = render_columns(&headers, &columns, Some(30), Overflow::Wrap);
+        assert_eq!(wrapped.len(), 4);
+        assert_eq!(wrapped[3], "applehelp>=1.0");
+
+        // too narrow for the columns
+        let stacked = render_columns(&headers, &columns, Some(20), Overflow::Truncate);
+        assert_eq!(
+            stacked,
+            cells(&[
+                "[Runtime]",
+                "  glibc",
+                "  python-sphinxcont…",
+                "[Make]",
+                "  git"
+            ])
+        );
+    }
+}
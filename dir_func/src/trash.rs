@@ -0,0 +1,268 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::git::git_output;
+use crate::links::is_aur_url;
+use crate::state::{now_secs, state_dir, STATE_DIR};
+
+// directory in the state dir, removed package dirs are moved to
+pub const TRASH_DIR: &str = "trash";
+
+// days a removed package dir is kept, overwritten by TrashRetention, 0 keeps them
+pub const DEFAULT_TRASH_RETENTION_DAYS: u64 = 14;
+
+// a removed package dir in the trash, stored as <removed>-<name>
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashEntry {
+    pub name: String,
+    // unix time of the removal
+    pub removed: u64,
+    pub path: PathBuf,
+}
+
+pub fn trash_dir(aur_path: &Path) -> PathBuf {
+    state_dir(aur_path).join(TRASH_DIR)
+}
+
+// the retention of the config, None keeps the removed dirs
+pub fn retention(config: &Config) -> Option<Duration> {
+    let days = match config.get("TrashRetention").map(|days| days.parse::<u64>()) {
+        Some(Ok(days)) => days,
+        Some(Err(_)) => {
+            println!("WARNING: TrashRetention isn't a number of days, using the default");
+            DEFAULT_TRASH_RETENTION_DAYS
+        }
+        None => DEFAULT_TRASH_RETENTION_DAYS,
+    };
+    match days {
+        0 => None,
+        days => Some(Duration::from_secs(days * 60 * 60 * 24)),
+    }
+}
+
+// checks, that the dir is a git checkout of an AUR package directly in the AUR dir, its origin
+// has to be the AUR of the config. A symlink is only accepted, if it points into the AUR dir, the
+// link itself is removed then
+pub fn verify_removable(aur_path: &Path, dir: &Path, config: &Config) -> Result<(), String> {
+    let aur_path = aur_path.canonicalize().map_err(|err| {
+        format!(
+            "couldn't resolve the AUR dir {}: {}",
+            aur_path.display(),
+            err
+        )
+    })?;
+    let name = dir
+        .file_name()
+        .ok_or_else(|| format!("{} has no directory name", dir.display()))?;
+    if name.to_string_lossy().starts_with('.') || name == STATE_DIR {
+        return Err(format!("{} is a hidden directory", dir.display()));
+    }
+    let parent = dir
+        .parent()
+        .and_then(|parent| parent.canonicalize().ok())
+        .ok_or_else(|| format!("couldn't resolve the parent of {}", dir.display()))?;
+    if parent != aur_path {
+        return Err(format!("{} isn't in the AUR dir", dir.display()));
+    }
+    let metadata = fs::symlink_metadata(dir)
+        .map_err(|err| format!("couldn't read {}: {}", dir.display(), err))?;
+    if metadata.file_type().is_symlink() {
+        let target = dir
+            .canonicalize()
+            .map_err(|err| format!("couldn't resolve the link {}: {}", dir.display(), err))?;
+        if !target.starts_with(&aur_path) {
+            return Err(format!(
+                "{} is a link to {} outside of the AUR dir",
+                dir.display(),
+                target.display()
+            ));
+        }
+    } else if !metadata.is_dir() {
+        return Err(format!("{} isn't a directory", dir.display()));
+    }
+    if !dir.join(".git").is_dir() || !dir.join("PKGBUILD").is_file() {
+        return Err(format!(
+            "{} isn't a git checkout of an AUR package",
+            dir.display()
+        ));
+    }
+    let origin = git_output(dir, &["config", "--get", "remote.origin.url"])
+        .map(|origin| origin.trim().to_string())
+        .map_err(|_| format!("{} has no origin remote", dir.display()))?;
+    if !is_aur_url(&origin, config) {
+        return Err(format!(
+            "{} is a checkout of {}, not of the AUR",
+            dir.display(),
+            origin
+        ));
+    }
+    Ok(())
+}
+
+// moves the package dir into the trash of the AUR dir, verified with verify_removable
+pub fn move_to_trash(aur_path: &Path, dir: &Path, config: &Config) -> Result<TrashEntry, String> {
+    verify_removable(aur_path, dir, config)?;
+    let name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let trash = trash_dir(aur_path);
+    fs::create_dir_all(&trash)
+        .map_err(|err| format!("couldn't create {}: {}", trash.display(), err))?;
+    let mut removed = now_secs();
+    // dirs of the same name removed in the same second
+    while trash.join(format!("{}-{}", removed, name)).exists() {
+        removed += 1;
+    }
+    let path = trash.join(format!("{}-{}", removed, name));
+    fs::rename(dir, &path).map_err(|err| format!("couldn't move {}: {}", dir.display(), err))?;
+    Ok(TrashEntry {
+        name,
+        removed,
+        path,
+    })
+}
+
+// deletes the package dir without the trash, verified with verify_removable. A symlink is
+// removed, not its target
+pub fn delete_dir(aur_path: &Path, dir: &Path, config: &Config) -> Result<(), String> {
+    verify_removable(aur_path, dir, config)?;
+    let result = if dir.is_symlink() {
+        fs::remove_file(dir)
+    } else {
+        fs::remove_dir_all(dir)
+    };
+    result.map_err(|err| format!("couldn't delete {}: {}", dir.display(), err))
+}
+
+// the removed dirs in the trash, the oldest first
+pub fn list_trash(aur_path: &Path) -> Vec<TrashEntry> {
+    let Ok(read_dir) = fs::read_dir(trash_dir(aur_path)) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<TrashEntry> = read_dir
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let (removed, name) = file_name.split_once('-')?;
+            Some(TrashEntry {
+                name: name.to_string(),
+                removed: removed.parse().ok()?,
+                path: entry.path(),
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| (a.removed, &a.name).cmp(&(b.removed, &b.name)));
+    entries
+}
+
+// moves the removed dir back into the AUR dir, fails if there is a dir of the name again
+pub fn restore(aur_path: &Path, entry: &TrashEntry) -> Result<PathBuf, String> {
+    let target = aur_path.join(&entry.name);
+    if fs::symlink_metadata(&target).is_ok() {
+        return Err(format!("{} exists already", target.display()));
+    }
+    fs::rename(&entry.path, &target)
+        .map_err(|err| format!("couldn't restore {}: {}", entry.name, err))?;
+    Ok(target)
+}
+
+// deletes the removed dirs older than the retention
+pub fn purge_expired(aur_path: &Path, retention: Duration) -> Vec<TrashEntry> {
+    let oldest = now_secs().saturating_sub(retention.as_secs());
+    let mut purged: Vec<TrashEntry> = Vec::new();
+    for entry in list_trash(aur_path) {
+        if entry.removed >= oldest {
+            continue;
+        }
+        // doesn't follow a moved symlink
+        let result = if entry.path.is_symlink() {
+            fs::remove_file(&entry.path)
+        } else {
+            fs::remove_dir_all(&entry.path)
+        };
+        match result {
+            Ok(()) => purged.push(entry),
+            Err(err) => println!(
+                "WARNING: couldn't delete {} from the trash: {}",
+                entry.path.display(),
+                err
+            ),
+        }
+    }
+    purged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    use crate::git::tests::git;
+
+    fn package_dir(path: &Path, origin: &str) {
+        fs::create_dir_all(path).unwrap();
+        git(path, &["init", "-q"]);
+        git(path, &["remote", "add", "origin", origin]);
+        fs::write(path.join("PKGBUILD"), "").unwrap();
+    }
+
+    #[test]
+    fn trash_test() {
+        let tmp_path = Path::new("/tmp/aur_helper_rs_test/trash_test");
+        let _ = fs::remove_dir_all(tmp_path);
+        let aur_path = tmp_path.join("aur");
+        let outside = tmp_path.join("home");
+        let config = Config::default();
+        package_dir(&aur_path.join("foo"), "https://aur.archlinux.org/foo.git");
+        package_dir(&outside, "https://aur.archlinux.org/home.git");
+        package_dir(
+            &aur_path.join("dotfiles"),
+            "https://github.com/user/dotfiles.git",
+        );
+        fs::create_dir_all(aur_path.join("notes")).unwrap();
+        symlink(&outside, aur_path.join("link")).unwrap();
+
+        assert!(verify_removable(&aur_path, &aur_path.join("notes"), &config).is_err());
+        assert!(verify_removable(&aur_path, &aur_path.join("link"), &config).is_err());
+        assert!(verify_removable(&aur_path, &outside, &config).is_err());
+        assert!(verify_removable(&aur_path, &aur_path.join(".aur_helper"), &config).is_err());
+        assert!(move_to_trash(&aur_path, &aur_path.join("link"), &config).is_err());
+        // an other git checkout in the AUR dir
+        assert!(verify_removable(&aur_path, &aur_path.join("dotfiles"), &config).is_err());
+        assert!(outside.join("PKGBUILD").is_file());
+
+        assert!(delete_dir(&aur_path, &aur_path.join("link"), &config).is_err());
+        package_dir(&aur_path.join("bar"), "aur@aur.archlinux.org:bar.git");
+        delete_dir(&aur_path, &aur_path.join("bar"), &config).unwrap();
+        assert!(!aur_path.join("bar").exists());
+
+        let entry = move_to_trash(&aur_path, &aur_path.join("foo"), &config).unwrap();
+        assert!(!aur_path.join("foo").exists());
+        assert_eq!(list_trash(&aur_path), vec![entry.clone()]);
+        assert!(purge_expired(&aur_path, Duration::from_secs(3600)).is_empty());
+
+        assert_eq!(restore(&aur_path, &entry).unwrap(), aur_path.join("foo"));
+        assert!(aur_path.join("foo").join("PKGBUILD").is_file());
+        assert!(list_trash(&aur_path).is_empty());
+
+        // an entry older than the retention is deleted
+        let entry = move_to_trash(&aur_path, &aur_path.join("foo"), &config).unwrap();
+        let old = trash_dir(&aur_path).join("1000-foo");
+        fs::rename(&entry.path, &old).unwrap();
+        let purged = purge_expired(&aur_path, Duration::from_secs(3600));
+        assert_eq!(purged.len(), 1);
+        assert!(!old.exists());
+
+        let config = Config::parse("[options]\nTrashRetention = 0").unwrap();
+        assert_eq!(retention(&config), None);
+        assert_eq!(
+            retention(&Config::default()),
+            Some(Duration::from_secs(14 * 24 * 60 * 60))
+        );
+
+        let _ = fs::remove_dir_all(tmp_path);
+    }
+}
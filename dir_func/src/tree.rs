@@ -0,0 +1,317 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use raur::{Raur, SearchBy};
+
+use crate::srcinfo::dep_name;
+use crate::{DepSource, DepSources};
+
+// where a node of the dependency tree comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeSource {
+    Installed,
+    Repo,
+    // downloaded to the AUR dir, but not installed
+    AurDir,
+    Aur,
+    // neither in the repos nor in the AUR
+    Missing,
+}
+
+impl fmt::Display for NodeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeSource::Installed => write!(f, "installed"),
+            NodeSource::Repo => write!(f, "repo"),
+            NodeSource::AurDir => write!(f, "AUR dir"),
+            NodeSource::Aur => write!(f, "AUR"),
+            NodeSource::Missing => write!(f, "NOT FOUND"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeNode {
+    // the dependency as the parent declares it, e.g. foo>=1.0
+    pub dep: String,
+    pub make: bool,
+    pub source: NodeSource,
+    // the dependency is one of its own ancestors
+    pub cycle: bool,
+    // the dependencies are already shown in an other branch
+    pub repeated: bool,
+    pub children: Vec<TreeNode>,
+}
+
+// the AUR packages in the tree by the dependency names, filled by fetch_aur_packages
+pub type AurPackages = HashMap<String, raur::Package>;
+
+// the dependencies of an AUR package, runtime first, true for the make dependencies
+fn package_deps(pkg: &raur::Package) -> Vec<(String, bool)> {
+    pkg.depends
+        .iter()
+        .map(|dep| (dep.clone(), false))
+        .chain(pkg.make_depends.iter().map(|dep| (dep.clone(), true)))
+        .collect()
+}
+
+// fetches the AUR packages of the tree of root, a level of the tree per info request. Names not
+// found are looked up by provides. Installed and repo packages aren't followed
+pub async fn fetch_aur_packages(
+    root: &str,
+    sources: &DepSources,
+) -> Result<AurPackages, raur::Error> {
+    let raur_handler = raur::Handle::new();
+    let mut packages = AurPackages::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut pending: Vec<String> = vec![root.to_string()];
+    while !pending.is_empty() {
+        let names: Vec<String> = pending
+            .drain(..)
+            .filter(|name| seen.insert(name.clone()))
+            .collect();
+        if names.is_empty() {
+            break;
+        }
+        let found = raur_handler.info(&names).await?;
+        for name in &names {
+            let pkg = match found.iter().find(|pkg| &pkg.name == name) {
+                Some(pkg) => Some(pkg.clone()),
+                None => raur_handler
+                    .search_by(name, SearchBy::Provides)
+                    .await?
+                    .into_iter()
+                    .find(|pkg| pkg.provides.iter().any(|provide| dep_name(provide) == name)),
+            };
+            let Some(pkg) = pkg else {
+                continue;
+            };
+            for (dep, _) in package_deps(&pkg) {
+                let dep = dep_name(&dep);
+                if sources.source(dep) == DepSource::Aur && !seen.contains(dep) {
+                    pending.push(dep.to_string());
+                }
+            }
+            packages.insert(name.clone(), pkg);
+        }
+    }
+    Ok(packages)
+}
+
+fn node_source(
+    name: &str,
+    sources: &DepSources,
+    packages: &AurPackages,
+    aur_path: &Path,
+) -> NodeSource {
+    match sources.source(name) {
+        DepSource::Installed => NodeSource::Installed,
+        DepSource::Repo => NodeSource::Repo,
+        DepSource::Aur => match packages.get(name) {
+            Some(pkg) if aur_path.join(&pkg.package_base).is_dir() => NodeSource::AurDir,
+            Some(_) => NodeSource::Aur,
+            None => NodeSource::Missing,
+        },
+    }
+}
+
+// builds the dependency tree of the root from the fetched AUR packages, only AUR packages are
+// expanded. Packages already expanded in an other branch are marked as repeated
+pub fn build_tree(
+    root: &str,
+    packages: &AurPackages,
+    sources: &DepSources,
+    aur_path: &Path,
+) -> TreeNode {
+    let mut expanded: HashSet<String> = HashSet::new();
+    let mut ancestors: Vec<String> = Vec::new();
+    build_node(
+        root,
+        false,
+        packages,
+        sources,
+        aur_path,
+        &mut ancestors,
+        &mut expanded,
+    )
+}
+
+fn build_node(
+    dep: &str,
+    make: bool,
+    packages: &AurPackages,
+    sources: &DepSources,
+    aur_path: &Path,
+    ancestors: &mut Vec<String>,
+    expanded: &mut HashSet<String>,
+) -> TreeNode {
+    let name = dep_name(dep).to_string();
+    let source = if ancestors.is_empty() {
+        // the root is shown with its AUR state, even if it's installed
+        match packages.get(&name) {
+            Some(pkg) if aur_path.join(&pkg.package_base).is_dir() => NodeSource::AurDir,
+            Some(_) => NodeSource::Aur,
+            None => NodeSource::Missing,
+        }
+    } else {
+        node_source(&name, sources, packages, aur_path)
+    };
+    let mut node = TreeNode {
+        dep: dep.to_string(),
+        make,
+        source,
+        cycle: ancestors.contains(&name),
+        repeated: false,
+        children: Vec::new(),
+    };
+    if node.cycle || !matches!(source, NodeSource::Aur | NodeSource::AurDir) {
+        return node;
+    }
+    if !expanded.insert(name.clone()) {
+        node.repeated = true;
+        return node;
+    }
+    let Some(pkg) = packages.get(&name) else {
+        return node;
+    };
+    ancestors.push(name);
+    for (child, child_make) in package_deps(pkg) {
+        node.children.push(build_node(
+            &child, child_make, packages, sources, aur_path, ancestors, expanded,
+        ));
+    }
+    ancestors.pop();
+    node
+}
+
+fn node_label(node: &TreeNode) -> String {
+    let mut label = format!("{} [{}]", node.dep, node.source);
+    if node.make {
+        label.push_str(" (make)");
+    }
+    if node.cycle {
+        label.push_str(" <- CYCLE");
+    }
+    if node.repeated {
+        label.push_str(" (see above)");
+    }
+    label
+}
+
+// the lines of the tree drawn with box characters
+pub fn render_tree(root: &TreeNode) -> Vec<String> {
+    let mut lines = vec![node_label(root)];
+    render_children(root, "", &mut lines);
+    lines
+}
+
+fn render_children(node: &TreeNode, prefix: &str, lines: &mut Vec<String>) {
+    for (index, child) in node.children.iter().enumerate() {
+        let last = index + 1 == node.children.len();
+        let (branch, indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        lines.push(format!("{}{}{}", prefix, branch, node_label(child)));
+        render_children(child, &format!("{}{}", prefix, indent), lines);
+    }
+}
+
+// the unsatisfiable dependencies and the cycles of the tree, as the chains of names
+pub fn find_problems(root: &TreeNode) -> (Vec<String>, Vec<String>) {
+    let mut missing: Vec<String> = Vec::new();
+    let mut cycles: Vec<String> = Vec::new();
+    let mut path: Vec<String> = Vec::new();
+    collect_problems(root, &mut path, &mut missing, &mut cycles);
+    (missing, cycles)
+}
+
+fn collect_problems(
+    node: &TreeNode,
+    path: &mut Vec<String>,
+    missing: &mut Vec<String>,
+    cycles: &mut Vec<String>,
+) {
+    let name = dep_name(&node.dep).to_string();
+    if node.source == NodeSource::Missing && !missing.contains(&node.dep) {
+        missing.push(node.dep.clone());
+    }
+    if node.cycle {
+        let start = path
+            .iter()
+            .position(|ancestor| ancestor == &name)
+            .unwrap_or(0);
+        let mut chain: Vec<String> = path[start..].to_vec();
+        chain.push(name);
+        cycles.push(chain.join(" -> "));
+        return;
+    }
+    path.push(name);
+    for child in &node.children {
+        collect_problems(child, path, missing, cycles);
+    }
+    path.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pacman_db::DbPackage;
+
+    fn aur_pkg(name: &str, depends: &[&str], make_depends: &[&str]) -> raur::Package {
+        raur::Package {
+            name: name.to_string(),
+            package_base: name.to_string(),
+            depends: depends.iter().map(|dep| dep.to_string()).collect(),
+            make_depends: make_depends.iter().map(|dep| dep.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn dependency_tree_test() {
+        let aur_path = Path::new("/tmp/aur_helper_rs_test/dependency_tree_test");
+        let _ = std::fs::remove_dir_all(aur_path);
+        std::fs::create_dir_all(aur_path.join("libbar")).unwrap();
+        let sources = DepSources {
+            installed: vec![DbPackage {
+                name: "glibc".to_string(),
+                ..Default::default()
+            }],
+            repo: vec![DbPackage {
+                name: "cmake".to_string(),
+                ..Default::default()
+            }],
+        };
+        let mut packages = AurPackages::new();
+        for pkg in [
+            aur_pkg("foo", &["glibc", "libbar>=2", "baz"], &["cmake"]),
+            aur_pkg("libbar", &["foo", "gone"], &[]),
+            aur_pkg("baz", &["libbar"], &[]),
+        ] {
+            packages.insert(pkg.name.clone(), pkg);
+        }
+
+        let tree = build_tree("foo", &packages, &sources, aur_path);
+        assert_eq!(
+            render_tree(&tree),
+            vec![
+                "foo [AUR]",
+                "├── glibc [installed]",
+                "├── libbar>=2 [AUR dir]",
+                "│   ├── foo [AUR] <- CYCLE",
+                "│   └── gone [NOT FOUND]",
+                "├── baz [AUR]",
+                "│   └── libbar [AUR dir] (see above)",
+                "└── cmake [repo] (make)",
+            ]
+        );
+        let (missing, cycles) = find_problems(&tree);
+        assert_eq!(missing, vec!["gone".to_string()]);
+        assert_eq!(cycles, vec!["foo -> libbar -> foo".to_string()]);
+
+        let _ = std::fs::remove_dir_all(aur_path);
+    }
+}
@@ -0,0 +1,168 @@
+use std::cmp::Ordering;
+
+// compares two version parts like rpmvercmp of pacman: alternating runs of digits and letters are
+// compared, separators only count by their length
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let (mut one, mut two) = (0, 0);
+
+    while one < a.len() && two < b.len() {
+        let sep_start_one = one;
+        let sep_start_two = two;
+        while one < a.len() && !a[one].is_ascii_alphanumeric() {
+            one += 1;
+        }
+        while two < b.len() && !b[two].is_ascii_alphanumeric() {
+            two += 1;
+        }
+        if one >= a.len() || two >= b.len() {
+            break;
+        }
+        // a different amount of separators decides
+        if one - sep_start_one != two - sep_start_two {
+            return (one - sep_start_one).cmp(&(two - sep_start_two));
+        }
+
+        let is_num = a[one].is_ascii_digit();
+        let segment = |s: &[u8], start: usize| -> usize {
+            let mut end = start;
+            while end < s.len()
+                && (if is_num {
+                    s[end].is_ascii_digit()
+                } else {
+                    s[end].is_ascii_alphabetic()
+                })
+            {
+                end += 1;
+            }
+            end
+        };
+        let end_one = segment(a, one);
+        let end_two = segment(b, two);
+        // different segment types, numbers are newer
+        if two == end_two {
+            return if is_num {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+
+        let mut seg_one = &a[one..end_one];
+        let mut seg_two = &b[two..end_two];
+        if is_num {
+            while seg_one.first() == Some(&b'0') {
+                seg_one = &seg_one[1..];
+            }
+            while seg_two.first() == Some(&b'0') {
+                seg_two = &seg_two[1..];
+            }
+            if seg_one.len() != seg_two.len() {
+                return seg_one.len().cmp(&seg_two.len());
+            }
+        }
+        match seg_one.cmp(seg_two) {
+            Ordering::Equal => {}
+            ordering => return ordering,
+        }
+        one = end_one;
+        two = end_two;
+    }
+
+    if one >= a.len() && two >= b.len() {
+        return Ordering::Equal;
+    }
+    // the remaining part decides: an alpha suffix is older, anything else newer
+    if (one >= a.len() && !b[two].is_ascii_alphabetic())
+        || (one < a.len() && a[one].is_ascii_alphabetic())
+    {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+// splits [epoch:]pkgver[-pkgrel] into its parts, the epoch defaults to 0
+fn parse_evr(version: &str) -> (&str, &str, Option<&str>) {
+    let (epoch, rest) = match version.split_once(':') {
+        Some((epoch, rest)) if epoch.bytes().all(|c| c.is_ascii_digit()) => (epoch, rest),
+        _ => ("0", version),
+    };
+    match rest.rsplit_once('-') {
+        Some((pkgver, pkgrel)) => (epoch, pkgver, Some(pkgrel)),
+        None => (epoch, rest, None),
+    }
+}
+
+// compares two package versions the way pacman (vercmp) does
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let (epoch_a, ver_a, rel_a) = parse_evr(a);
+    let (epoch_b, ver_b, rel_b) = parse_evr(b);
+    rpmvercmp(epoch_a, epoch_b)
+        .then_with(|| rpmvercmp(ver_a, ver_b))
+        .then_with(|| match (rel_a, rel_b) {
+            (Some(rel_a), Some(rel_b)) => rpmvercmp(rel_a, rel_b),
+            _ => Ordering::Equal,
+        })
+}
+
+// splits a package file name like 'foo-bar-1:2.0-3-x86_64.pkg.tar.zst' into name and version
+pub fn parse_artifact_name(file_name: &str) -> Option<(String, String)> {
+    let stem = &file_name[..file_name.find(".pkg.tar")?];
+    let mut parts = stem.rsplitn(4, '-');
+    let _arch = parts.next()?;
+    let pkgrel = parts.next()?;
+    let pkgver = parts.next()?;
+    let name = parts.next()?;
+    Some((name.to_string(), format!("{}-{}", pkgver, pkgrel)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vercmp_test() {
+        // test cases of pacman's vercmptest.sh
+        let cases = [
+            ("1.5.0", "1.5.0", Ordering::Equal),
+            ("1.5.1", "1.5.0", Ordering::Greater),
+            ("1.5.1", "1.5", Ordering::Greater),
+            ("1.5.0-1", "1.5.0-2", Ordering::Less),
+            ("1.5.0-1", "1.5.1-1", Ordering::Less),
+            ("1.5-1", "1.5", Ordering::Equal),
+            ("1.0a", "1.0alpha", Ordering::Less),
+            ("1.0a", "1.0", Ordering::Less),
+            ("1.0rc1", "1.0", Ordering::Less),
+            ("1.0", "1.0.a", Ordering::Less),
+            ("1.0.a", "1.0.1", Ordering::Less),
+            ("1.1", "1.1.a", Ordering::Less),
+            ("1.01", "1.1", Ordering::Equal),
+            ("1.0..0", "1.0.0", Ordering::Greater),
+            ("1:1.0", "2.0", Ordering::Greater),
+            ("0:1.0", "1.0", Ordering::Equal),
+            ("1.0-1", "1:0.1-1", Ordering::Less),
+            ("r1234.abcdef-1", "r999.abcdef-1", Ordering::Greater),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(vercmp(a, b), expected, "vercmp({}, {})", a, b);
+            assert_eq!(vercmp(b, a), expected.reverse(), "vercmp({}, {})", b, a);
+        }
+    }
+
+    #[test]
+    fn parse_artifact_name_test() {
+        assert_eq!(
+            parse_artifact_name("python-foo.bar-1:2.0.r3-1-x86_64.pkg.tar.zst"),
+            Some(("python-foo.bar".to_string(), "1:2.0.r3-1".to_string()))
+        );
+        assert_eq!(parse_artifact_name("foo-1.0-1.tar.gz"), None);
+    }
+}
@@ -1,11 +1,18 @@
-use clap::{Arg, ArgMatches};
+use clap::{Arg, ArgGroup, ArgMatches};
+use dir_func::config::Config;
+use dir_func::git::{Divergence, UpdateStrategy};
+use dir_func::hooks::{self, HookPhase};
+use dir_func::pacman_db::{self, PACMAN_CONF_PATH, PACMAN_DB_PATH};
 use dir_func::*;
+use dir_func::{
+    date, links, pgp, process, rdeps, search, select, srcinfo, state, table, trash, tree,
+};
 
-use std::{collections::HashSet, env, io, path::PathBuf};
-
-pub struct Config {
-    aur_dir: String,
-}
+use std::{
+    collections::HashSet,
+    env, io,
+    path::{Path, PathBuf},
+};
 
 fn get_fallback_aur_dir() -> String {
     let mut home_dir = match env::var_os("HOME") {
@@ -40,13 +47,13 @@ pub struct Cli {
 impl Cli {
     pub fn new(config_file: Option<Config>) -> Self {
         let default_dir = match config_file {
-            Some(config) => config.aur_dir,
+            Some(config) => config.aur_dir().unwrap_or_else(get_fallback_aur_dir),
             None => get_fallback_aur_dir(),
         };
         Self { default_dir }
     }
     pub fn get_aur_dir(&self) -> String {
-        return self.default_dir.clone();
+        self.default_dir.clone()
     }
     // build the CLI with clap, -> pacman as inspiration
     pub fn get_cli_command(self) -> clap::Command {
@@ -75,6 +82,51 @@ impl Cli {
             .value_parser(clap::builder::PathBufValueParser::new())
             .value_hint(clap::ValueHint::DirPath)
             .help("The path to the aur-directories");
+        let db_path_arg = Arg::new("dbpath")
+            .long("dbpath")
+            .global(true)
+            .default_value(PACMAN_DB_PATH)
+            .value_parser(clap::builder::PathBufValueParser::new())
+            .value_hint(clap::ValueHint::DirPath)
+            .help("The path to the pacman database, containing the sync and local dbs");
+        let repo_arg = Arg::new("repo")
+            .short('p')
+            .long("repo")
+            .action(clap::ArgAction::SetTrue)
+            .help("checks, which packages are now available in the official repositories");
+        let switch_arg = Arg::new("switch")
+            .long("switch")
+            .requires("repo")
+            .action(clap::ArgAction::SetTrue)
+            .help("installs the packages available in the repositories from there and removes their directorys, CALLS SUDO!");
+        let makepkg_args_arg = Arg::new("makepkg_args")
+            .long("makepkg-args")
+            .value_name("ARGS")
+            .allow_hyphen_values(true)
+            .help("additional arguments for makepkg, e.g. --makepkg-args='--nocheck -f'");
+        let sandbox_arg = Arg::new("sandbox")
+            .long("sandbox")
+            .action(clap::ArgAction::SetTrue)
+            .help("builds in a bubblewrap sandbox: read-only root, hidden home, network only for the source download");
+        let chroot_arg = Arg::new("chroot")
+            .long("chroot")
+            .conflicts_with("sandbox")
+            .action(clap::ArgAction::SetTrue)
+            .help("builds in a copy of a clean build root in the state dir with only the declared dependencies, CALLS SUDO!");
+        let reinstall_arg = Arg::new("reinstall")
+            .long("reinstall")
+            .action(clap::ArgAction::SetTrue)
+            .help("installs the packages even if the same version is already installed");
+        let rebuild_arg = Arg::new("rebuild")
+            .long("rebuild")
+            .action(clap::ArgAction::SetTrue)
+            .help("builds the packages even if the current version is already built");
+        let strategy_arg = Arg::new("strategy")
+            .long("strategy")
+            .value_name("STRATEGY")
+            .default_value("ask")
+            .value_parser(["ask", "stash", "rebase", "reset", "skip"])
+            .help("how to update repos with local changes, local commits or a rewritten upstream: stash and reapply the changes, rebase the local commits, reset hard to the AUR or skip the package");
         let search_arg = Arg::new("search")
             .short('s')
             .action(clap::ArgAction::SetTrue)
@@ -84,15 +136,69 @@ impl Cli {
             .value_hint(clap::ValueHint::Other)
             .action(clap::ArgAction::Set)
             .value_parser(clap::builder::StringValueParser::new())
-            .help("a package name to search for")
-            .num_args(1);
+            .help(
+                "package names to show, the extended search finds the packages matching all terms",
+            )
+            .num_args(1..);
+        let info_arg = Arg::new("info")
+            .short('i')
+            .long("info")
+            .conflicts_with("search")
+            .action(clap::ArgAction::SetTrue)
+            .help(
+                "shows the details of all given packages with one request, the default without -s",
+            );
+        let date_format_arg = Arg::new("date_format")
+            .long("date-format")
+            .value_name("FORMAT")
+            .default_value(date::DEFAULT_DATE_FORMAT)
+            .help("the format of the dates in the package details: %Y, %m, %d, %H, %M, %S in UTC or %s for the unix timestamp");
+        let wrap_arg = Arg::new("wrap")
+            .long("wrap")
+            .action(clap::ArgAction::SetTrue)
+            .help("wraps long dependencies in the package details instead of cutting them");
+        let search_by_arg = Arg::new("by")
+            .long("by")
+            .value_name("FIELD")
+            .requires("search")
+            .default_value("name-desc")
+            .value_parser(search::SEARCH_FIELDS)
+            .help("the field the extended search searches in");
+        let sort_arg = Arg::new("sort")
+            .long("sort")
+            .value_name("KEY")
+            .requires("search")
+            .value_parser(["votes", "popularity", "modified", "name"])
+            .help("sorts the results of the extended search, by default in the order of the AUR");
+        let limit_arg = Arg::new("limit")
+            .long("limit")
+            .value_name("N")
+            .requires("search")
+            .value_parser(clap::value_parser!(usize))
+            .help("shows only the first N results of the extended search");
+        let select_download_arg = Arg::new("download")
+            .short('d')
+            .long("download")
+            .requires("search")
+            .action(clap::ArgAction::SetTrue)
+            .help("numbers the results of the extended search and asks, which packages to download, e.g. '1 3 5-7' or '^4'");
+        let select_arg = Arg::new("select")
+            .long("select")
+            .value_name("SELECTION")
+            .requires("search")
+            .allow_hyphen_values(true)
+            .help("downloads the selected results of the extended search without asking, e.g. --select='1 3 5-7'");
         let download_links_arg = Arg::new("download_links")
             .required(true)
             .num_args(0..)
             //.value_hint(clap::ValueHint::Other)
             .action(clap::ArgAction::Set)
             //.value_parser(clap::builder::StringValueParser::new())
-            .help("a link or list of links for repos to clone; uses the AUR, if only a package name is provided");
+            .help("package names, aur:<pkg>, github:<user>/<repo> or git urls of repos to clone");
+        let ssh_arg = Arg::new("ssh")
+            .long("ssh")
+            .action(clap::ArgAction::SetTrue)
+            .help("clones the AUR packages with the ssh url (AurSshUrl) for pushing as maintainer");
 
         // end arguments
         //
@@ -103,39 +209,126 @@ impl Cli {
             .long_flag("download")
             .about("Clones the Repos to the AUR dir")
             .arg(build_arg.clone())
+            .arg(makepkg_args_arg.clone())
+            .arg(rebuild_arg.clone())
+            .arg(sandbox_arg.clone())
+            .arg(chroot_arg.clone())
             .arg(install_arg.clone())
+            .arg(reinstall_arg.clone())
+            .arg(ssh_arg.clone())
             .arg(download_links_arg);
         let check = clap::Command::new("check")
             .short_flag('C')
             .long_flag("check")
             .about("checks, which packages are actually installed")
             .arg(remove_arg.clone())
+            .arg(repo_arg)
+            .arg(switch_arg)
             .arg(aur_packet_arg.clone());
         let install = clap::Command::new("install")
             .short_flag('I')
             .long_flag("install")
             .about("generates the pacman command and installs the LAST BUILD packages, CALLS SUDO!")
+            .arg(reinstall_arg.clone())
             .arg(aur_packet_arg.clone());
         let update = clap::Command::new("update")
             .short_flag('U')
             .long_flag("update")
             .about("updates the git repos in the directory")
+            .arg(strategy_arg)
             .arg(build_arg.clone())
+            .arg(makepkg_args_arg.clone())
+            .arg(rebuild_arg.clone())
+            .arg(sandbox_arg.clone())
+            .arg(chroot_arg.clone())
             .arg(install_arg.clone())
+            .arg(reinstall_arg.clone())
             .arg(aur_packet_arg.clone());
         let build = clap::Command::new("build")
             .short_flag('B')
             .long_flag("build")
             .about("builds the packages recursively")
+            .arg(makepkg_args_arg.clone())
+            .arg(rebuild_arg.clone())
+            .arg(sandbox_arg.clone())
+            .arg(chroot_arg.clone())
             .arg(install_arg.clone())
+            .arg(reinstall_arg.clone())
             .arg(aur_packet_arg.clone());
-        // TODO: optional: download after search and select afterward
         let search = clap::Command::new("search")
             .short_flag('S')
             .long_flag("search")
             .about("searches for packages by a given name and shows informations about the package")
             .arg(search_name_arg)
-            .arg(search_arg);
+            .arg(search_arg)
+            .arg(info_arg)
+            .arg(date_format_arg.clone())
+            .arg(wrap_arg)
+            .arg(search_by_arg)
+            .arg(sort_arg)
+            .arg(limit_arg)
+            .arg(select_download_arg)
+            .arg(select_arg)
+            // downloading, building and installing only work on selected results
+            .group(
+                ArgGroup::new("selection")
+                    .args(["download", "select"])
+                    .multiple(true),
+            )
+            .arg(build_arg.clone().requires("selection"))
+            .arg(makepkg_args_arg.clone().requires("selection"))
+            .arg(rebuild_arg.clone().requires("selection"))
+            .arg(sandbox_arg.clone().requires("selection"))
+            .arg(chroot_arg.clone().requires("selection"))
+            // -Si is the package info, installing selected results is only --install
+            .arg(install_arg.clone().short(None).requires("selection"))
+            .arg(reinstall_arg.clone().requires("selection"))
+            .arg(ssh_arg.requires("selection"));
+        let status = clap::Command::new("status")
+            .about(
+                "shows the git, version, install and build state of the packages, changes nothing",
+            )
+            .arg(aur_packet_arg.clone());
+        let rdeps = clap::Command::new("rdeps")
+            .about("shows the packages in the AUR dir and the installed ones, which depend on a package")
+            .arg(
+                Arg::new("rdeps_pkg")
+                    .value_name("PACKAGE")
+                    .required(true)
+                    .help("a package name, a provided name or a package dir"),
+            );
+        let tree = clap::Command::new("tree")
+            .about("shows the runtime and make dependency tree of an AUR package")
+            .arg(
+                Arg::new("tree_pkg")
+                    .value_name("PACKAGE")
+                    .required(true)
+                    .help("the AUR package"),
+            );
+        let undo_remove = clap::Command::new("undo-remove")
+            .about("restores a package dir removed to the trash, lists the trash without a name")
+            .arg(
+                Arg::new("removed_dir")
+                    .value_name("DIR")
+                    .help("the name of the removed package dir, the latest removal is restored"),
+            )
+            .arg(date_format_arg);
+        let remove = clap::Command::new("remove")
+            .about("removes the installed packages of a pkgbase with pacman -Rns and the dependencies not needed anymore")
+            .arg(
+                Arg::new("remove_pkg")
+                    .value_name("PACKAGE")
+                    .required(true)
+                    .help("the pkgbase or one of its packages"),
+            )
+            .arg(
+                Arg::new("dir_action")
+                    .long("dir")
+                    .value_name("ACTION")
+                    .value_parser(["keep", "trash", "delete"])
+                    .default_value("keep")
+                    .help("keeps the package dir, moves it to the trash or deletes it"),
+            );
         let get_aur_dir = clap::Command::new("get-aur-dir").hide(true);
         // end subcommands
 
@@ -143,139 +336,381 @@ impl Cli {
             .about("a simple aur package helper for updating, building and installing AUR packages in a directory")
             // .arg_required_else_help(true)
             .arg(aur_path_arg)
+            .arg(db_path_arg)
             .subcommand_required(true)
             .subcommand(update)
             .subcommand(build)
             .subcommand(install)
             .subcommand(check)
+            .subcommand(status)
             .subcommand(search)
+            .subcommand(tree)
+            .subcommand(rdeps)
+            .subcommand(remove)
+            .subcommand(undo_remove)
             .subcommand(get_aur_dir)
             .subcommand(download)
     }
 }
 
-fn string_to_link(package: &String) -> String {
-    let mut aur_link = "https://aur.archlinux.org/".to_owned();
-    if package.find("/").is_none() {
-        aur_link.push_str(package);
-        aur_link.push_str(".git");
-        aur_link.to_owned()
-    } else {
-        package.to_owned()
+// clones the packages and builds and installs them with -b and -i, the specs are package names,
+// aur:<pkg>, github:<user>/<repo> or git urls
+pub async fn download_command(
+    aur_path: &Path,
+    specs: Vec<String>,
+    sub_matches: ArgMatches,
+    config: &Config,
+) {
+    let ssh = sub_matches.get_flag("ssh");
+    let mut links: Vec<String> = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    for spec in &specs {
+        match links::aur_name(spec, config) {
+            Some(name) => names.push(name.to_string()),
+            None => match links::resolve_link(spec, config, ssh) {
+                Ok(link) => links.push(link),
+                Err(err) => println!("ERROR: not downloading {}", err),
+            },
+        }
     }
-}
 
-pub fn download_command(aur_path: &PathBuf, sub_matches: ArgMatches) {
-    let links: Vec<String> = sub_matches
-        .get_many::<String>("download_links")
-        .expect("search_name argument required but couldn't get it")
-        .map(|item| string_to_link(item))
-        .collect();
+    // the requested packages of each AUR link, split packages are cloned from their package base
+    let mut requested: Vec<(String, Vec<String>)> = Vec::new();
+    if !names.is_empty() {
+        let groups = match resolve_pkgbases(&names).await {
+            Ok((groups, not_found)) => {
+                for name in not_found {
+                    println!("ERROR: not downloading '{}', it isn't in the AUR", name);
+                }
+                groups
+            }
+            Err(err) => {
+                println!(
+                    "WARNING: couldn't look up the package bases, using the names, error: \n {}",
+                    err
+                );
+                names
+                    .iter()
+                    .map(|name| (name.clone(), Vec::new()))
+                    .collect()
+            }
+        };
+        for (pkgbase, pkgnames) in groups {
+            if pkgnames.iter().any(|name| name != &pkgbase) {
+                println!("{} is part of {}", pkgnames.join(", "), pkgbase);
+            }
+            match links::aur_link(&pkgbase, config, ssh) {
+                Ok(link) if !links.contains(&link) => {
+                    links.push(link.clone());
+                    requested.push((link, pkgnames));
+                }
+                Ok(_) => {}
+                Err(err) => println!("ERROR: not downloading {}", err),
+            }
+        }
+    }
+    if links.is_empty() {
+        return;
+    }
 
-    let download_dirs = download_packages_from_git(aur_path, links);
+    let outcomes = download_packages_from_git(aur_path, links, &fetch_options(config));
     let build = sub_matches.get_flag("build");
 
-    let (download_dirs, err) = match download_dirs {
-        Ok(cloned_dirs) => {
-            println!("Downloaded packages: \n {:?}", cloned_dirs);
-            (cloned_dirs, false)
+    let mut cloned: Vec<PathBuf> = Vec::new();
+    let mut existing: Vec<PathBuf> = Vec::new();
+    let mut cancelled: Vec<String> = Vec::new();
+    let mut err = false;
+    for (link, outcome) in outcomes {
+        if let DownloadOutcome::Cloned(dir) | DownloadOutcome::Existing(dir) = &outcome {
+            record_requested(dir, &requested, &link);
         }
-        Err((cloned_dirs, failed_links)) => {
-            println!("ERROR downloading packages!\n Failed downloads: \n {:?} \n Successfull downloads: \n {:?}", failed_links, cloned_dirs);
-            (cloned_dirs, true)
+        match outcome {
+            DownloadOutcome::Cloned(dir) => {
+                println!(" cloned   {} -> {}", link, dir.display());
+                cloned.push(dir);
+            }
+            DownloadOutcome::Existing(dir) => {
+                println!(" existing {} -> {}", link, dir.display());
+                existing.push(dir);
+            }
+            DownloadOutcome::Failed(reason) => {
+                println!("ERROR downloading {}: {}", link, reason);
+                err = true;
+            }
+            DownloadOutcome::Cancelled => cancelled.push(link),
         }
-    };
+    }
+    if !cancelled.is_empty() {
+        println!("Cancelled downloads: \n {:?}", cancelled);
+        return;
+    }
+
+    let mut download_dirs = cloned.clone();
+    if !existing.is_empty()
+        && confirm_ask(Some("Update the existing checkouts?".to_owned())).is_ok()
+    {
+        let mut choose_strategy =
+            |dir: &Path, divergence: &Divergence| ask_strategy(dir, divergence);
+        let results = update_packages(existing, config, &mut choose_strategy);
+        for (path, reason) in &results.failed {
+            println!("ERROR updating {}: {}", path.display(), reason);
+            err = true;
+        }
+        println!("Updated packages: \n {:?}", results.updated);
+        if !results.cancelled.is_empty() {
+            println!("Cancelled packages: \n {:?}", results.cancelled);
+            return;
+        }
+        download_dirs.extend(results.updated);
+    }
 
     if build {
         if err {
             if confirm_ask(Some("Remove downloaded Packages?".to_owned())).is_ok() {
-                remove_command(download_dirs.clone());
+                remove_command(aur_path, cloned.clone(), config);
+                download_dirs.retain(|dir| !cloned.contains(dir));
             } else {
                 return;
             }
         }
-        if confirm_ask(None).is_err() {
+        if download_dirs.is_empty() || confirm_ask(None).is_err() {
             return;
         }
-        build_command(download_dirs, sub_matches);
+        build_command(download_dirs, sub_matches, config);
     }
 }
 
-pub fn update_command(dirs: Vec<PathBuf>, sub_matches: ArgMatches) {
-    let updated_dirs = update_packages(dirs.clone());
+// records the packages the user asked for of the package base cloned from the link
+fn record_requested(dir: &Path, requested: &[(String, Vec<String>)], link: &str) {
+    let Some((_, pkgnames)) = requested
+        .iter()
+        .find(|(requested_link, _)| requested_link == link)
+    else {
+        return;
+    };
+    if pkgnames.is_empty() {
+        return;
+    }
+    if let Err(err) = state::add_requested(dir, pkgnames) {
+        println!(
+            "WARNING: couldn't record the requested packages of {}: {}",
+            dir.display(),
+            err
+        );
+    }
+}
+
+pub fn update_command(dirs: Vec<PathBuf>, sub_matches: ArgMatches, config: &Config) {
+    let strategy = sub_matches
+        .get_one::<String>("strategy")
+        .expect("strategy has a default value but couldn't get it");
+    let mut choose_strategy = |dir: &Path, divergence: &Divergence| match strategy.parse() {
+        Ok(strategy) => strategy,
+        Err(_) => ask_strategy(dir, divergence),
+    };
+    let results = update_packages(dirs, config, &mut choose_strategy);
     let build = sub_matches.get_flag("build");
 
-    let (updated_dirs, err) = match updated_dirs {
-        Ok(paths) => (paths, false),
-        Err(err_paths) => {
-            println!("ERROR in paths: \n {:?}\n", err_paths.clone());
-            (
-                get_set_diff(dirs, err_paths.into_iter().map(|x| x.0).collect()),
-                true,
-            )
+    let err = !results.failed.is_empty();
+    if err {
+        println!("ERROR in paths:");
+        for (path, reason) in &results.failed {
+            println!(" {}: {}", path.display(), reason);
         }
-    };
-    println!("Updated packages: \n {:?}", updated_dirs);
+        println!();
+    }
+    println!("Updated packages: \n {:?}", results.updated);
+    if !results.cancelled.is_empty() {
+        println!("Cancelled packages: \n {:?}", results.cancelled);
+        return;
+    }
 
     if build {
-        if err {
-            if confirm_ask(None).is_err() {
-                return;
-            }
+        if err && confirm_ask(None).is_err() {
+            return;
+        }
+        build_command(results.updated, sub_matches, config);
+    }
+}
+
+// asks how to update a package repo, which can't be fast-forwarded
+fn ask_strategy(dir: &Path, divergence: &Divergence) -> UpdateStrategy {
+    println!("{} can't be fast-forwarded: {}", dir.display(), divergence);
+    loop {
+        println!("Update strategy? [stash|rebase|reset|skip] (default: skip)");
+        let mut input = String::new();
+        if let Err(err) = io::stdin().read_line(&mut input) {
+            println!("IO-error: {:?}", err);
+            return UpdateStrategy::Skip;
+        }
+        if process::cancelled() {
+            return UpdateStrategy::Skip;
+        }
+        match input.trim() {
+            "" => return UpdateStrategy::Skip,
+            answer => match answer.parse() {
+                Ok(strategy) => return strategy,
+                Err(err) => println!("{}", err),
+            },
         }
-        build_command(updated_dirs, sub_matches);
     }
 }
 
-pub fn build_command(dirs: Vec<PathBuf>, sub_matches: ArgMatches) {
-    let build_pkgs = build_packages(dirs.clone());
+pub fn build_command(dirs: Vec<PathBuf>, sub_matches: ArgMatches, config: &Config) {
+    let options = BuildOptions {
+        makepkg_args: sub_matches
+            .get_one::<String>("makepkg_args")
+            .map(|args| args.split_whitespace().map(|arg| arg.to_string()).collect())
+            .unwrap_or_default(),
+        rebuild: sub_matches.get_flag("rebuild"),
+        sandbox: sub_matches.get_flag("sandbox"),
+        chroot: sub_matches.get_flag("chroot"),
+    };
+    check_pgp_keys(&dirs, config);
+    let results = build_packages(dirs, config, &options);
     let install = sub_matches.get_flag("install");
 
-    let (build_pkgs, err) = match build_pkgs {
-        Ok(paths) => (paths, false),
-        Err(err_paths) => {
-            println!("ERROR building some packages: \n {:?}", err_paths);
-            (
-                get_set_diff(dirs, err_paths.into_iter().map(|x| x.0).collect()),
-                true,
-            )
+    println!("Built packages: \n {:?}", results.built);
+    if !results.skipped.is_empty() {
+        println!(
+            "Skipped packages (already built, use --rebuild to build them anyway): \n {:?}",
+            results.skipped
+        );
+    }
+    let err = !results.failed.is_empty();
+    if err {
+        println!("ERROR building some packages:");
+        for (path, reason) in results.failed {
+            println!(" {}: {}", path.display(), reason);
         }
-    };
+    }
+    if !results.cancelled.is_empty() {
+        println!("Cancelled packages: \n {:?}", results.cancelled);
+        return;
+    }
     if install {
-        if err {
-            if confirm_ask(None).is_err() {
-                return;
-            }
+        if err && confirm_ask(None).is_err() {
+            return;
         }
-        install_command(build_pkgs);
+        let mut build_pkgs = results.built;
+        build_pkgs.extend(results.skipped);
+        install_command(build_pkgs, sub_matches, config);
     }
 }
 
-pub fn install_command(dirs: Vec<PathBuf>) {
-    let install_cmd = install_packages(dirs);
-    let mut install_cmd = match install_cmd {
-        Ok(cmd) => cmd,
-        Err((cmd, err_paths)) => {
+// reports changed validpgpkeys since the last build and offers to import the missing keys
+fn check_pgp_keys(dirs: &[PathBuf], config: &Config) {
+    let mut missing: Vec<(PathBuf, String)> = Vec::new();
+    for dir in dirs {
+        let check = match pgp::check_keys(dir) {
+            Ok(check) => check,
+            Err(_) => continue,
+        };
+        if check.changed() {
             println!(
-                "Error on some Packages (not found or a read error): \n {:?}",
-                err_paths
+                "WARNING: the PGP keys of {} changed since the last build, added: {:?}, removed: {:?}, replaced key files: {:?}",
+                dir.display(),
+                check.added,
+                check.removed,
+                check.replaced
             );
-            match confirm_ask(None) {
-                Ok(_) => cmd,
-                Err(_) => return,
-            }
         }
+        missing.extend(check.missing.into_iter().map(|key| (dir.clone(), key)));
+    }
+    if missing.is_empty() {
+        return;
+    }
+    println!("Missing PGP keys:");
+    for (dir, key) in &missing {
+        let source = pgp::find_key_source(dir, config.keyring_dir().as_deref(), key);
+        println!(" {} ({}) from {}", key, dir.display(), source);
+    }
+    if confirm_ask(Some("Import the missing keys?".to_owned())).is_err() {
+        return;
+    }
+    for (dir, key) in missing {
+        match pgp::import_key(&dir, config, &key) {
+            Ok(source) => println!("Imported {} from {}", key, source),
+            Err(err) => println!("WARNING: couldn't import {}: {}", key, err),
+        }
+    }
+}
+
+pub fn install_command(dirs: Vec<PathBuf>, sub_matches: ArgMatches, config: &Config) {
+    let options = InstallOptions {
+        db_path: sub_matches
+            .get_one::<PathBuf>("dbpath")
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from(PACMAN_DB_PATH)),
+        reinstall: sub_matches.get_flag("reinstall"),
     };
+    let plan = install_packages(dirs, config, &options);
+    if !plan.failed.is_empty() {
+        println!(
+            "Error on some Packages (not found, a read error or vetoed by a hook): \n {:?}",
+            plan.failed
+        );
+        if confirm_ask(None).is_err() {
+            return;
+        }
+    }
+    if !plan.unchanged.is_empty() {
+        println!("Already installed (use --reinstall to install them anyway):");
+        for entry in &plan.unchanged {
+            println!(" {} {}", entry.name, entry.version);
+        }
+    }
+    let mut install_cmd = match plan.command() {
+        Some(cmd) => cmd,
+        None => {
+            println!("Nothing to install");
+            return;
+        }
+    };
+    println!("Packages to install:");
+    for entry in &plan.install {
+        match &entry.installed {
+            Some(installed) if entry.is_downgrade() => println!(
+                " {} {} -> {} (downgrade)",
+                entry.name, installed, entry.version
+            ),
+            Some(installed) if entry.is_unchanged() => println!(
+                " {} {} -> {} (reinstall)",
+                entry.name, installed, entry.version
+            ),
+            Some(installed) => println!(" {} {} -> {}", entry.name, installed, entry.version),
+            None => println!(" {} (new) {}", entry.name, entry.version),
+        }
+    }
     println!("Calling the following command: \n {:?}", install_cmd);
-    match confirm_ask(None) {
-        Ok(_) => {
-            install_cmd.status().expect("Error calling pacman");
+    if confirm_ask(None).is_ok() {
+        let outcome =
+            match process::run_status(&mut install_cmd, config.timeout("InstallTimeout", None)) {
+                Ok(status) if status.success() => "success".to_string(),
+                Ok(status) => format!("failed: {}", status),
+                Err(err) => {
+                    println!("ERROR calling pacman: {}", err);
+                    format!("failed: {}", err)
+                }
+            };
+        for dir in plan.dirs() {
+            if let Err(err) = hooks::run_hooks(config, HookPhase::PostInstall, &dir, &outcome) {
+                println!("WARNING: {}: {}", dir.display(), err);
+            }
         }
-        Err(_) => return,
     }
 }
 
-pub fn check_command(dirs: Vec<PathBuf>, sub_matches: ArgMatches) {
+pub fn check_command(
+    aur_path: &Path,
+    dirs: Vec<PathBuf>,
+    sub_matches: ArgMatches,
+    config: &Config,
+) {
+    if sub_matches.get_flag("repo") {
+        repo_check_command(aur_path, dirs, sub_matches, config);
+        return;
+    }
     let inst_pkgs = check_installed(dirs.clone());
     let remove = sub_matches.get_flag("remove");
 
@@ -302,41 +737,646 @@ pub fn check_command(dirs: Vec<PathBuf>, sub_matches: ArgMatches) {
         )
     }
     if remove {
-        let dirs = dirs_set.into_iter().collect();
-        remove_command(dirs);
+        let dirs: Vec<PathBuf> = dirs_set.into_iter().collect();
+        let db_path = sub_matches
+            .get_one::<PathBuf>("dbpath")
+            .expect("dbpath has a default value but couldn't get it");
+        warn_needed_dirs(aur_path, &dirs, db_path);
+        remove_command(aur_path, dirs, config);
+    }
+}
+
+// warns about the dirs to remove, which other packages of the AUR dir or installed packages still
+// need
+fn warn_needed_dirs(aur_path: &Path, dirs: &[PathBuf], db_path: &Path) {
+    let all_dirs = get_dirs(aur_path, false).unwrap_or_default();
+    let infos = rdeps::read_srcinfos(&all_dirs);
+    let installed = pacman_db::read_local_db(db_path).unwrap_or_default();
+    for dir in dirs {
+        let Some(name) = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+        else {
+            continue;
+        };
+        let names = rdeps::provided_names(&name, &infos, &installed);
+        let needed_by: Vec<String> = rdeps::reverse_deps(&names, &infos, &installed, dirs)
+            .into_iter()
+            .filter(|rdep| rdep.kind.required())
+            .map(|rdep| format!("{} ({} {})", rdep.pkgname, rdep.kind, rdep.dep))
+            .collect();
+        if !needed_by.is_empty() {
+            println!(
+                "WARNING: {} is still needed by {}",
+                name,
+                needed_by.join(", ")
+            );
+        }
     }
 }
 
-pub fn remove_command(dirs: Vec<PathBuf>) {
-    let cmd = remove_uninstalled_dirs(dirs);
-    match cmd {
-        Some(mut c) => {
-            print!("Calling: \n{}", c.get_program().to_str().unwrap());
-            for arg in c.get_args() {
-                print!(" {}", arg.to_str().unwrap());
+pub fn rdeps_command(aur_path: &Path, sub_matches: ArgMatches) {
+    let pkg = sub_matches
+        .get_one::<String>("rdeps_pkg")
+        .expect("rdeps_pkg argument required but couldn't get it");
+    let db_path = sub_matches
+        .get_one::<PathBuf>("dbpath")
+        .expect("dbpath has a default value but couldn't get it");
+    let dirs = match get_dirs(aur_path, false) {
+        Ok(dirs) => dirs,
+        Err(err) => {
+            println!(
+                "ERROR: Couldn't get the directories in the AUR-Directory, error: \n {}",
+                err
+            );
+            return;
+        }
+    };
+    let infos = rdeps::read_srcinfos(&dirs);
+    let installed = pacman_db::read_local_db(db_path).unwrap_or_else(|err| {
+        println!("WARNING: couldn't read the local pacman database: {}", err);
+        Vec::new()
+    });
+    let names = rdeps::provided_names(pkg, &infos, &installed);
+    // the packages of the queried dir don't count
+    let own_dir: Vec<PathBuf> = infos
+        .iter()
+        .filter(|(dir, info)| {
+            dir.file_name().is_some_and(|name| name == pkg.as_str()) || &info.pkgbase == pkg
+        })
+        .map(|(dir, _)| dir.clone())
+        .collect();
+    let rdeps = rdeps::reverse_deps(&names, &infos, &installed, &own_dir);
+    if rdeps.is_empty() {
+        println!("No package depends on {} ({})", pkg, names.join(", "));
+        return;
+    }
+    println!("Packages depending on {} ({}):", pkg, names.join(", "));
+    for rdep in rdeps {
+        let location = match &rdep.dir {
+            Some(dir) => format!("AUR dir {}", dir.display()),
+            None => "installed".to_string(),
+        };
+        println!(
+            " {} [{}] {} {}",
+            rdep.pkgname, location, rdep.kind, rdep.dep
+        );
+    }
+}
+
+pub fn repo_check_command(
+    aur_path: &Path,
+    dirs: Vec<PathBuf>,
+    sub_matches: ArgMatches,
+    config: &Config,
+) {
+    let db_path = sub_matches
+        .get_one::<PathBuf>("dbpath")
+        .expect("dbpath has a default value but couldn't get it");
+    let switch = sub_matches.get_flag("switch");
+
+    let repo_moves = match find_repo_packages(dirs, db_path) {
+        Ok(moves) => moves,
+        Err(err) => {
+            println!("ERROR: Couldn't read the pacman sync databases: \n {}", err);
+            return;
+        }
+    };
+    if repo_moves.is_empty() {
+        println!("No package of the AUR dir is available in the repositories");
+        return;
+    }
+    let (moved, partial): (Vec<RepoMove>, Vec<RepoMove>) = repo_moves
+        .into_iter()
+        .partition(|repo_move| repo_move.complete());
+
+    let print_move = |repo_move: &RepoMove| {
+        for pkg in &repo_move.repo_pkgs {
+            println!(
+                "{} -> {}/{} {}",
+                repo_move
+                    .dir
+                    .file_name()
+                    .expect("couldn't get filename!")
+                    .to_str()
+                    .unwrap(),
+                pkg.repo,
+                pkg.name,
+                pkg.version
+            );
+        }
+    };
+    if !moved.is_empty() {
+        println!("\nPackages available in the repositories: \n");
+        moved.iter().for_each(print_move);
+    }
+    if !partial.is_empty() {
+        println!(
+            "\nPackages partially available in the repositories, keeping their directorys: \n"
+        );
+        for repo_move in &partial {
+            print_move(repo_move);
+            println!("  still only in the AUR: {}", repo_move.aur_only.join(" "));
+        }
+    }
+
+    if switch {
+        if moved.is_empty() {
+            println!("No package moved completely to the repositories");
+            return;
+        }
+        // only reinstall the packages which are actually installed
+        let installed: HashSet<String> = match pacman_db::read_local_db(db_path) {
+            Ok(pkgs) => pkgs.into_iter().map(|pkg| pkg.name).collect(),
+            Err(err) => {
+                println!("ERROR: Couldn't read the local pacman database: \n {}", err);
+                return;
             }
-            println!("");
-            match confirm_ask(None) {
-                Ok(_) => {
-                    c.status().expect("error on removing");
+        };
+        let pkg_names: Vec<String> = moved
+            .iter()
+            .flat_map(|repo_move| repo_move.repo_pkgs.iter().map(|pkg| pkg.name.clone()))
+            .filter(|name| installed.contains(name))
+            .collect();
+        if let Some(mut inst_cmd) = repo_install_cmd(pkg_names) {
+            println!("Calling the following command: \n {:?}", inst_cmd);
+            if confirm_ask(None).is_err() {
+                return;
+            }
+            match process::run_status(&mut inst_cmd, None) {
+                Ok(status) if status.success() => {}
+                _ => {
+                    println!("ERROR installing the repo packages, keeping the directorys");
+                    return;
                 }
-                Err(_) => return,
             }
         }
+        remove_command(
+            aur_path,
+            moved.into_iter().map(|repo_move| repo_move.dir).collect(),
+            config,
+        );
+    }
+}
+
+pub async fn status_command(dirs: Vec<PathBuf>, sub_matches: ArgMatches) {
+    let db_path = sub_matches
+        .get_one::<PathBuf>("dbpath")
+        .expect("dbpath has a default value but couldn't get it");
+
+    let mut status_vec = get_package_status(dirs, db_path, Path::new(PACMAN_CONF_PATH));
+    status_vec.sort_by(|a, b| a.dir.cmp(&b.dir));
+    let pkgnames: Vec<String> = status_vec
+        .iter()
+        .flat_map(|status| status.pkgnames.clone())
+        .collect();
+    match get_aur_versions(pkgnames).await {
+        Ok(versions) => {
+            for status in status_vec.iter_mut() {
+                status.aur_version = versions.get(&status.pkgbase).cloned();
+            }
+        }
+        Err(err) => println!(
+            "WARNING: couldn't get the versions from the AUR: \n {}",
+            err
+        ),
+    }
+
+    let none = || "-".to_string();
+    let mut rows: Vec<[String; 8]> = vec![[
+        "Package".to_string(),
+        "Git".to_string(),
+        "Local".to_string(),
+        "Installed".to_string(),
+        "Latest build".to_string(),
+        "AUR".to_string(),
+        "Held/Ignored".to_string(),
+        "Last build".to_string(),
+    ]];
+    for status in status_vec {
+        let mut pinned: Vec<&str> = Vec::new();
+        if status.held {
+            pinned.push("held");
+        }
+        if status.ignored {
+            pinned.push("ignored");
+        }
+        rows.push([
+            status
+                .dir
+                .file_name()
+                .expect("couldn't get filename!")
+                .to_string_lossy()
+                .to_string(),
+            status
+                .git
+                .map(|git| git.to_string())
+                .unwrap_or("no git repo".to_string()),
+            status.local_version.unwrap_or_else(none),
+            status.installed_version.unwrap_or_else(none),
+            status
+                .latest_build
+                .and_then(|path| path.file_name().map(|f| f.to_string_lossy().to_string()))
+                .unwrap_or_else(none),
+            status.aur_version.unwrap_or_else(none),
+            if pinned.is_empty() {
+                none()
+            } else {
+                pinned.join(", ")
+            },
+            status
+                .last_build
+                .map(|record| {
+                    let result = if record.success {
+                        "success".to_string()
+                    } else {
+                        format!("failed ({})", record.status)
+                    };
+                    let age = date::format_age(state::now_secs().saturating_sub(record.time));
+                    format!("{}, {}", result, age)
+                })
+                .unwrap_or_else(none),
+        ]);
+    }
+
+    let mut widths = [0; 8];
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{: <width$}", cell, width = width))
+            .collect();
+        println!("{}", line.join(" | ").trim_end());
+    }
+}
+
+// moves the package dirs to the trash of the AUR dir after a confirmation, dirs which aren't
+// AUR package checkouts in the AUR dir are refused
+pub fn remove_command(aur_path: &Path, dirs: Vec<PathBuf>, config: &Config) {
+    let mut removable: Vec<PathBuf> = Vec::new();
+    for dir in dirs {
+        match trash::verify_removable(aur_path, &dir, config) {
+            Ok(()) => removable.push(dir),
+            Err(err) => println!("ERROR: not removing {}: {}", dir.display(), err),
+        }
+    }
+    if removable.is_empty() {
+        println!("No unused directory, everything is installed");
+        return;
+    }
+    let retention = trash::retention(config);
+    println!("Moving to {}:", trash::trash_dir(aur_path).display());
+    for dir in &removable {
+        println!("  {}", dir.display());
+    }
+    match retention {
+        Some(retention) => println!(
+            "They are deleted after {} days",
+            retention.as_secs() / (60 * 60 * 24)
+        ),
+        None => println!("They are kept until the trash is emptied"),
+    }
+    if confirm_ask(None).is_err() {
+        return;
+    }
+    for dir in &removable {
+        match trash::move_to_trash(aur_path, dir, config) {
+            Ok(entry) => println!(
+                "Removed {}, restore it with: aur_helper undo-remove {}",
+                dir.display(),
+                entry.name
+            ),
+            Err(err) => println!("ERROR removing {}: {}", dir.display(), err),
+        }
+    }
+    if let Some(retention) = retention {
+        for entry in trash::purge_expired(aur_path, retention) {
+            println!("Deleted {} from the trash", entry.path.display());
+        }
+    }
+}
+
+// removes the installed packages of a pkgbase with the dependencies not needed anymore, the
+// packages are also removed from the LocalRepo and the package dir is kept, trashed or deleted
+pub fn remove_package_command(aur_path: &Path, sub_matches: ArgMatches, config: &Config) {
+    let pkg = sub_matches
+        .get_one::<String>("remove_pkg")
+        .expect("remove_pkg argument required but couldn't get it");
+    let dir_action = sub_matches
+        .get_one::<String>("dir_action")
+        .expect("dir_action has a default value but couldn't get it");
+    let db_path = sub_matches
+        .get_one::<PathBuf>("dbpath")
+        .expect("dbpath has a default value but couldn't get it");
+    let installed = match pacman_db::read_local_db(db_path) {
+        Ok(installed) => installed,
+        Err(err) => {
+            println!("ERROR: Couldn't read the local pacman database: \n {}", err);
+            return;
+        }
+    };
+    let (pkgbase, pkg_names) = match pacman_db::installed_split_packages(pkg, &installed) {
+        Some(split) => split,
         None => {
-            println!("No unused directory, everything is installed")
+            println!("{} isn't installed", pkg);
+            (pkg.clone(), Vec::new())
+        }
+    };
+    let dir = match aur_path.join(&pkgbase) {
+        dir if dir.is_dir() => Some(dir),
+        _ => rdeps::read_srcinfos(&get_dirs(aur_path, false).unwrap_or_default())
+            .into_iter()
+            .find(|(_, info)| info.pkgbase == pkgbase)
+            .map(|(dir, _)| dir),
+    };
+
+    let mut rm_cmd = remove_packages_cmd(&pkg_names, db_path);
+    if rm_cmd.is_some() {
+        let targets = match removal_targets(&pkg_names, db_path) {
+            Ok(targets) => targets,
+            Err(err) => {
+                println!(
+                    "ERROR: pacman can't remove {}: \n {}",
+                    pkg_names.join(" "),
+                    err
+                );
+                return;
+            }
+        };
+        let deps: Vec<&String> = targets
+            .iter()
+            .filter(|target| !pkg_names.contains(target))
+            .collect();
+        println!("Packages of {} to remove: {}", pkgbase, pkg_names.join(" "));
+        if deps.is_empty() {
+            println!("No dependency is removed with them");
+        } else {
+            println!(
+                "Dependencies removed with them: {}",
+                deps.iter()
+                    .map(|dep| dep.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+        }
+    }
+
+    // the packages of the local repo, the built ones of the dir may not be installed
+    let mut repo_names = pkg_names.clone();
+    if let Some(info) = dir.as_ref().and_then(|dir| srcinfo::read_srcinfo(dir).ok()) {
+        for package in info.packages {
+            if !repo_names.contains(&package.pkgname) {
+                repo_names.push(package.pkgname);
+            }
+        }
+    }
+    let mut repo_cmd = config
+        .local_repo()
+        .and_then(|repo_db| repo_remove_cmd(&repo_db, &repo_names));
+    if let Some(repo_cmd) = &repo_cmd {
+        println!("Updating the local repository: \n {:?}", repo_cmd);
+    }
+    let dir = dir.filter(|_| dir_action != "keep");
+    if let Some(dir) = &dir {
+        match dir_action.as_str() {
+            "trash" => println!("Moving {} to the trash", dir.display()),
+            _ => println!("Deleting {}", dir.display()),
+        }
+    }
+    if rm_cmd.is_none() && repo_cmd.is_none() && dir.is_none() {
+        println!("Nothing to remove for {}", pkg);
+        return;
+    }
+    if confirm_ask(None).is_err() {
+        return;
+    }
+
+    if let Some(rm_cmd) = rm_cmd.as_mut() {
+        match process::run_status(rm_cmd, config.timeout("InstallTimeout", None)) {
+            Ok(status) if status.success() => {}
+            _ => {
+                println!("ERROR removing the packages of {}", pkgbase);
+                return;
+            }
+        }
+    }
+    if let Some(repo_cmd) = repo_cmd.as_mut() {
+        match process::run_status(repo_cmd, None) {
+            Ok(status) if status.success() => {}
+            _ => println!("ERROR removing the packages from the local repository"),
+        }
+    }
+    if let Some(dir) = dir {
+        let result = match dir_action.as_str() {
+            "trash" => trash::move_to_trash(aur_path, &dir, config).map(|entry| {
+                println!(
+                    "Removed {}, restore it with: aur_helper undo-remove {}",
+                    dir.display(),
+                    entry.name
+                );
+            }),
+            _ => trash::delete_dir(aur_path, &dir, config),
+        };
+        if let Err(err) = result {
+            println!("ERROR: {}", err);
         }
     }
 }
-pub async fn search_command(sub_matches: ArgMatches) {
-    let ext_search = sub_matches.get_flag("search");
-    let search_name: &String = sub_matches
-        .get_one::<String>("search_name")
-        .expect("search_name argument required but couldn't get it");
-    if ext_search {
-        ext_search_aur(search_name).await;
+
+// restores the latest removed dir of the name, without a name the trash is listed
+pub fn undo_remove_command(aur_path: &Path, sub_matches: ArgMatches, config: &Config) {
+    let entries = trash::list_trash(aur_path);
+    let date_format = sub_matches
+        .get_one::<String>("date_format")
+        .expect("date_format has a default value but couldn't get it");
+    let Some(name) = sub_matches.get_one::<String>("removed_dir") else {
+        if entries.is_empty() {
+            println!("The trash is empty");
+            return;
+        }
+        let retention = trash::retention(config);
+        for entry in &entries {
+            let mut line = format!(
+                "{} (removed {})",
+                entry.name,
+                date::format_date(entry.removed as i64, date_format)
+            );
+            if let Some(retention) = retention {
+                line.push_str(&format!(
+                    ", deleted {}",
+                    date::format_date((entry.removed + retention.as_secs()) as i64, date_format)
+                ));
+            }
+            println!("{}", line);
+        }
+        return;
+    };
+    let Some(entry) = entries.iter().rev().find(|entry| &entry.name == name) else {
+        println!("ERROR: {} isn't in the trash", name);
+        return;
+    };
+    match trash::restore(aur_path, entry) {
+        Ok(dir) => println!("Restored {}", dir.display()),
+        Err(err) => println!("ERROR restoring {}: {}", name, err),
+    }
+}
+// the package names to show the details of with one info request, None for the extended search
+pub fn info_request(sub_matches: &ArgMatches) -> Option<Vec<String>> {
+    if sub_matches.get_flag("search") {
+        return None;
+    }
+    Some(
+        sub_matches
+            .get_many::<String>("search_name")
+            .expect("search_name argument required but couldn't get it")
+            .cloned()
+            .collect(),
+    )
+}
+
+pub async fn search_command(aur_path: &Path, sub_matches: ArgMatches, config: &Config) {
+    if let Some(names) = info_request(&sub_matches) {
+        let db_path = sub_matches
+            .get_one::<PathBuf>("dbpath")
+            .expect("dbpath has a default value but couldn't get it");
+        let options = InfoOptions {
+            date_format: sub_matches
+                .get_one::<String>("date_format")
+                .expect("date_format has a default value but couldn't get it")
+                .clone(),
+            width: table::terminal_width(),
+            overflow: if sub_matches.get_flag("wrap") {
+                table::Overflow::Wrap
+            } else {
+                table::Overflow::Truncate
+            },
+            deps: DepSources::load(db_path),
+        };
+        search_aur(&names, &options).await;
+        return;
+    }
+    let terms: Vec<String> = sub_matches
+        .get_many::<String>("search_name")
+        .expect("search_name argument required but couldn't get it")
+        .cloned()
+        .collect();
+    let options = search::SearchOptions {
+        by: sub_matches
+            .get_one::<String>("by")
+            .and_then(|by| search::parse_search_by(by).ok())
+            .unwrap_or_default(),
+        sort: sub_matches
+            .get_one::<String>("sort")
+            .and_then(|sort| sort.parse().ok()),
+        limit: sub_matches.get_one::<usize>("limit").copied(),
+    };
+    let selection = sub_matches.get_one::<String>("select").cloned();
+    if selection.is_some() || sub_matches.get_flag("download") {
+        select_download(aur_path, &terms, &options, selection, sub_matches, config).await;
     } else {
-        search_aur(search_name).await;
+        ext_search_aur(&terms, &options, aur_path).await;
+    }
+}
+
+// numbers the results of the extended search and downloads the selection of --select or the one
+// the user enters, then builds and installs like download
+async fn select_download(
+    aur_path: &Path,
+    terms: &[String],
+    options: &search::SearchOptions,
+    selection: Option<String>,
+    sub_matches: ArgMatches,
+    config: &Config,
+) {
+    let pkgs = match search::search(terms, options).await {
+        Ok(pkgs) => pkgs,
+        Err(err) => {
+            println!("Error while searching for {}: \n {}", terms.join(" "), err);
+            return;
+        }
+    };
+    if pkgs.is_empty() {
+        println!("Couldn't find a package for '{}'", terms.join(" "));
+        return;
+    }
+    for (index, pkg) in pkgs.iter().enumerate() {
+        print!("[{}] ", index + 1);
+        print_simple_pkg_info(pkg.clone(), in_aur_dir(aur_path, pkg));
+    }
+    let selection = match selection {
+        Some(selection) => selection,
+        None => {
+            println!("Packages to download? (e.g. 1 3 5-7, ^4 for all but 4)");
+            let mut input = String::new();
+            if let Err(err) = io::stdin().read_line(&mut input) {
+                println!("IO-error: {:?}", err);
+                return;
+            }
+            if process::cancelled() {
+                return;
+            }
+            input
+        }
+    };
+    let indices = match select::parse_selection(&selection, pkgs.len()) {
+        Ok(indices) => indices,
+        Err(err) => {
+            println!("ERROR: invalid selection: {}", err);
+            return;
+        }
+    };
+    if indices.is_empty() {
+        println!("Nothing selected");
+        return;
+    }
+    let names: Vec<String> = indices
+        .into_iter()
+        .map(|index| pkgs[index].name.clone())
+        .collect();
+    println!("Downloading: {}", names.join(" "));
+    download_command(aur_path, names, sub_matches, config).await;
+}
+
+pub async fn tree_command(aur_path: &Path, sub_matches: ArgMatches) {
+    let pkg = sub_matches
+        .get_one::<String>("tree_pkg")
+        .expect("tree_pkg argument required but couldn't get it");
+    let db_path = sub_matches
+        .get_one::<PathBuf>("dbpath")
+        .expect("dbpath has a default value but couldn't get it");
+    let sources = DepSources::load(db_path);
+    let packages = match tree::fetch_aur_packages(pkg, &sources).await {
+        Ok(packages) => packages,
+        Err(err) => {
+            println!(
+                "Error while fetching the dependencies of {}: \n {}",
+                pkg, err
+            );
+            return;
+        }
+    };
+    if !packages.contains_key(pkg) {
+        println!("Couldn't find a package named '{}', try -Ss.", pkg);
+        return;
+    }
+    let root = tree::build_tree(pkg, &packages, &sources, aur_path);
+    for line in tree::render_tree(&root) {
+        println!("{}", line);
+    }
+    let (missing, cycles) = tree::find_problems(&root);
+    if !missing.is_empty() {
+        println!(
+            "WARNING: unsatisfiable dependencies, neither in the repos nor in the AUR: {}",
+            missing.join(", ")
+        );
+    }
+    for cycle in cycles {
+        println!("WARNING: dependency cycle: {}", cycle);
     }
 }
 
@@ -346,16 +1386,19 @@ fn confirm_ask(msg: Option<String>) -> Result<(), ()> {
     let mut input = String::new();
     match io::stdin().read_line(&mut input) {
         Ok(_) => {
-            if input == "\n" || input == "Y" {
-                return Ok(());
+            if process::cancelled() {
+                println!("Aborting");
+                Err(())
+            } else if input == "\n" || input == "Y" {
+                Ok(())
             } else {
                 println!("Aborting");
-                return Err(());
+                Err(())
             }
         }
         Err(err) => {
             println!("IO-error: {:?}", err);
-            return Err(());
+            Err(())
         }
     }
 }
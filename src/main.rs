@@ -1,6 +1,10 @@
-use std::{io, path::PathBuf};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 use clap::ArgMatches;
+use dir_func::config::Config;
 
 mod cli;
 
@@ -13,7 +17,18 @@ mod cli;
 // TODO: improve code-structure
 #[tokio::main]
 async fn main() {
-    let cli = cli::Cli::new(None);
+    dir_func::process::install_interrupt_handler();
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            println!(
+                "WARNING: Couldn't read the config file, using the defaults, error: \n {}",
+                err
+            );
+            Config::default()
+        }
+    };
+    let cli = cli::Cli::new(Some(config.clone()));
     let dir = cli.get_aur_dir();
     let command_matches = cli.get_cli_command().get_matches();
 
@@ -21,7 +36,7 @@ async fn main() {
         .get_one::<PathBuf>("AUR_PATH")
         .expect("AUR_PATH argument is required but not found!");
 
-    let local_commands = ["update", "build", "install", "check"];
+    let local_commands = ["update", "build", "install", "check", "status"];
 
     match command_matches.subcommand() {
         // Some(("update", sub_matches))
@@ -41,18 +56,36 @@ async fn main() {
             };
 
             match cmd {
-                "update" => cli::update_command(pkg_dirs, sub_matches.to_owned()),
-                "build" => cli::build_command(pkg_dirs, sub_matches.to_owned()),
-                "install" => cli::install_command(pkg_dirs),
-                "check" => cli::check_command(pkg_dirs, sub_matches.to_owned()),
+                "update" => cli::update_command(pkg_dirs, sub_matches.to_owned(), &config),
+                "build" => cli::build_command(pkg_dirs, sub_matches.to_owned(), &config),
+                "install" => cli::install_command(pkg_dirs, sub_matches.to_owned(), &config),
+                "check" => cli::check_command(path, pkg_dirs, sub_matches.to_owned(), &config),
+                "status" => cli::status_command(pkg_dirs, sub_matches.to_owned()).await,
                 _ => unreachable!(),
             }
         }
         Some(("search", sub_matches)) => {
-            cli::search_command(sub_matches.to_owned()).await;
+            cli::search_command(path, sub_matches.to_owned(), &config).await;
+        }
+        Some(("rdeps", sub_matches)) => {
+            cli::rdeps_command(path, sub_matches.to_owned());
+        }
+        Some(("remove", sub_matches)) => {
+            cli::remove_package_command(path, sub_matches.to_owned(), &config);
+        }
+        Some(("undo-remove", sub_matches)) => {
+            cli::undo_remove_command(path, sub_matches.to_owned(), &config);
+        }
+        Some(("tree", sub_matches)) => {
+            cli::tree_command(path, sub_matches.to_owned()).await;
         }
         Some(("download", sub_matches)) => {
-            cli::download_command(path, sub_matches.to_owned());
+            let specs: Vec<String> = sub_matches
+                .get_many::<String>("download_links")
+                .expect("download_links argument required but couldn't get it")
+                .cloned()
+                .collect();
+            cli::download_command(path, specs, sub_matches.to_owned(), &config).await;
         }
         Some(("get-aur-dir", _)) => {
             println!("{dir}");
@@ -64,11 +97,11 @@ async fn main() {
     }
 }
 
-fn get_dirs(aur_path: &PathBuf, sub_matches: &ArgMatches) -> Result<Vec<PathBuf>, io::Error> {
+fn get_dirs(aur_path: &Path, sub_matches: &ArgMatches) -> Result<Vec<PathBuf>, io::Error> {
     let aur_package: Vec<PathBuf> = match sub_matches.get_many::<String>("AUR_PACKAGES") {
         Some(packages) => packages
             .map_while(|pkg_str| {
-                let mut pkg_path = aur_path.clone();
+                let mut pkg_path = aur_path.to_path_buf();
                 pkg_path.push(pkg_str);
                 if !pkg_path.is_dir() {
                     print!(
@@ -82,7 +115,7 @@ fn get_dirs(aur_path: &PathBuf, sub_matches: &ArgMatches) -> Result<Vec<PathBuf>
                 Some(pkg_path)
             })
             .collect(),
-        None => dir_func::get_dirs(aur_path.as_path(), true)?,
+        None => dir_func::get_dirs(aur_path, true)?,
     };
     if aur_package.is_empty() {
         return Err(io::Error::new(
@@ -97,3 +130,28 @@ fn get_dirs(aur_path: &PathBuf, sub_matches: &ArgMatches) -> Result<Vec<PathBuf>
 fn cli_test() {
     cli::Cli::new(None).get_cli_command().debug_assert();
 }
+
+#[test]
+fn search_flags_test() {
+    let parse = |args: &[&str]| {
+        cli::Cli::new(None)
+            .get_cli_command()
+            .try_get_matches_from(args)
+    };
+    // -Si shows the details of all packages with one info request
+    let matches = parse(&["aur_helper", "-Si", "a", "b", "c"]).unwrap();
+    let (_, sub_matches) = matches.subcommand().unwrap();
+    assert_eq!(
+        cli::info_request(sub_matches),
+        Some(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+    );
+    assert!(parse(&["aur_helper", "-Ssi", "foo"]).is_err());
+    // installing needs selected results
+    assert!(parse(&["aur_helper", "-S", "--install", "foo"]).is_err());
+    assert!(parse(&["aur_helper", "-S", "-b", "foo"]).is_err());
+    assert!(parse(&["aur_helper", "-S", "--ssh", "foo"]).is_err());
+    assert!(parse(&["aur_helper", "-S", "--install", "-s", "foo"]).is_err());
+    assert!(parse(&["aur_helper", "-Ss", "foo", "-d", "--install"]).is_ok());
+    assert!(parse(&["aur_helper", "-Ss", "foo", "--select=1", "-b"]).is_ok());
+    assert!(parse(&["aur_helper", "-S", "foo", "bar"]).is_ok());
+}